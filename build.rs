@@ -7,3 +7,6 @@ fn main() {
     res.set_icon("icon.ico");
     res.compile().unwrap();
 }
+
+#[cfg(not(windows))]
+fn main() {}
@@ -0,0 +1,443 @@
+use base64::{engine::general_purpose, Engine as _};
+use rcgen::{
+    BasicConstraints, CertificateParams, DnType, IsCa, Issuer, KeyPair,
+    KeyUsagePurpose,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tiny_http::{Header, Method, Request, Response, Server, SslConfig};
+use tungstenite::handshake::derive_accept_key;
+use tungstenite::protocol::Role;
+use tungstenite::{Message, WebSocket};
+
+use std::collections::HashMap;
+use std::error::Error as StdError;
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+pub type MockResult<T> = Result<T, Box<dyn StdError + Send + Sync>>;
+
+// WAMP 1.0 event message type id, as pushed by the LCU
+const WAMP_EVENT: i64 = 8;
+
+const CHAMP_SELECT_SESSION_URI: &str = "/lol-champ-select/v1/session";
+const GAMEFLOW_PHASE_URI: &str = "/lol-gameflow/v1/gameflow-phase";
+
+// Open event sockets, each fed by its own thread
+type Subscribers = Arc<Mutex<Vec<Sender<String>>>>;
+
+// Everything the mock answers with, tests and fixtures script it freely
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct MockState {
+    pub summoner: Value,
+    pub gameflow_phase: String,
+    // None answers champ select endpoints with 404, as outside champ select
+    pub session: Option<Value>,
+    pub current_champion: i64,
+    pub pickable_skin_ids: Vec<i64>,
+    // Skins by champion id
+    pub inventories: HashMap<i64, Value>,
+    // Captured my-selection PATCH bodies, in order
+    pub selections: Vec<Value>,
+    // Riot Client product sessions, for running the mock as a Riot Client
+    pub product_sessions: Value,
+    // Holds every response back this long, to exercise client timeouts
+    pub stall_ms: u64,
+    // Canned answers by path, e.g. malformed bodies, served before routing
+    pub responses: HashMap<String, MockResponse>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct MockResponse {
+    pub status: u16,
+    pub body: String,
+}
+
+// One step of a scripted champ select, applied after waiting delay_ms
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct TimelineStep {
+    pub delay_ms: u64,
+    pub gameflow_phase: Option<String>,
+    // Merged into the current session like a JSON merge patch, arrays are
+    // replaced whole
+    pub session: Option<Value>,
+    pub end_session: bool,
+    pub current_champion: Option<i64>,
+    pub pickable_skin_ids: Option<Vec<i64>>,
+}
+
+pub struct MockLcu {
+    pub port: u16,
+    pub token: String,
+    // CA the server certificate is issued from, for pinning it like the
+    // Riot root
+    pub ca_pem: String,
+    pub state: Arc<Mutex<MockState>>,
+    subscribers: Subscribers,
+    server: Arc<Server>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl MockLcu {
+    // Serves HTTPS on a random port with a certificate from a fresh CA
+    pub fn start(token: &str, state: MockState) -> MockResult<Self> {
+        let (ca_pem, ssl_config) = issue_certificate()?;
+        let server = Server::https("127.0.0.1:0", ssl_config)?;
+        let port = server
+            .server_addr()
+            .to_ip()
+            .map(|addr| addr.port())
+            .ok_or("Mock LCU is not listening on an ip address")?;
+
+        let server = Arc::new(server);
+        let state = Arc::new(Mutex::new(state));
+        let expected_auth = format!(
+            "Basic {}",
+            general_purpose::STANDARD.encode(format!("riot:{}", token))
+        );
+
+        let subscribers = Subscribers::default();
+        let handle = {
+            let server = server.clone();
+            let state = state.clone();
+            let subscribers = subscribers.clone();
+            thread::spawn(move || {
+                for request in server.incoming_requests() {
+                    handle_request(
+                        request,
+                        &expected_auth,
+                        &state,
+                        &subscribers,
+                    );
+                }
+            })
+        };
+
+        Ok(Self {
+            port,
+            token: token.to_string(),
+            ca_pem,
+            state,
+            subscribers,
+            server,
+            handle: Some(handle),
+        })
+    }
+
+    pub fn update(&self, f: impl FnOnce(&mut MockState)) {
+        update(&self.state, &self.subscribers, f);
+    }
+
+    pub fn apply(&self, step: &TimelineStep) {
+        thread::sleep(Duration::from_millis(step.delay_ms));
+        self.update(|state| {
+            if let Some(phase) = &step.gameflow_phase {
+                state.gameflow_phase = phase.clone();
+            }
+            if let Some(champion) = step.current_champion {
+                state.current_champion = champion;
+            }
+            if let Some(skin_ids) = &step.pickable_skin_ids {
+                state.pickable_skin_ids = skin_ids.clone();
+            }
+            if let Some(patch) = &step.session {
+                let session = state.session.get_or_insert(json!({}));
+                merge(session, patch);
+            }
+            if step.end_session {
+                state.session = None;
+            }
+        });
+    }
+
+    pub fn play(&self, timeline: &[TimelineStep]) {
+        for step in timeline {
+            self.apply(step);
+        }
+    }
+
+    pub fn selections(&self) -> Vec<Value> {
+        self.state
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .selections
+            .clone()
+    }
+}
+
+impl Drop for MockLcu {
+    fn drop(&mut self) {
+        self.server.unblock();
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+// A throwaway CA and a certificate for the loopback address issued by it
+fn issue_certificate() -> MockResult<(String, SslConfig)> {
+    let ca_key = KeyPair::generate()?;
+    let mut ca_params = CertificateParams::new(Vec::new())?;
+    ca_params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+    ca_params
+        .distinguished_name
+        .push(DnType::CommonName, "Mock LCU Root CA");
+    ca_params.key_usages = vec![
+        KeyUsagePurpose::KeyCertSign,
+        KeyUsagePurpose::DigitalSignature,
+    ];
+    let ca = ca_params.self_signed(&ca_key)?;
+    let issuer = Issuer::new(ca_params, ca_key);
+
+    let key = KeyPair::generate()?;
+    let cert = CertificateParams::new(vec![
+        "127.0.0.1".to_string(),
+        "localhost".to_string(),
+    ])?
+    .signed_by(&key, &issuer)?;
+    Ok((
+        ca.pem(),
+        SslConfig {
+            certificate: cert.pem().into_bytes(),
+            private_key: key.serialize_pem().into_bytes(),
+        },
+    ))
+}
+
+fn handle_request(
+    mut request: Request,
+    expected_auth: &str,
+    state: &Mutex<MockState>,
+    subscribers: &Subscribers,
+) {
+    let authorized = request.headers().iter().any(|header| {
+        header.field.equiv("Authorization") && header.value == expected_auth
+    });
+    let websocket_key = request
+        .headers()
+        .iter()
+        .find(|header| header.field.equiv("Sec-WebSocket-Key"))
+        .map(|header| header.value.to_string());
+    if let (true, Some(key)) = (authorized, websocket_key) {
+        accept_websocket(request, &key, subscribers);
+        return;
+    }
+
+    let (status, body) = if authorized {
+        let mut body = String::new();
+        let _ = request.as_reader().read_to_string(&mut body);
+        let (stall, response) = update(state, subscribers, |state| {
+            let response = route(state, request.method(), request.url(), &body);
+            (state.stall_ms, response)
+        });
+        thread::sleep(Duration::from_millis(stall));
+        response
+    } else {
+        lcu_error(401, "UNAUTHORIZED", "Invalid credentials")
+    };
+
+    let mut response = Response::from_string(body).with_status_code(status);
+    if let Ok(header) = Header::from_bytes("Content-Type", "application/json") {
+        response.add_header(header);
+    }
+    let _ = request.respond(response);
+}
+
+// Changes to the session or gameflow phase are pushed to event sockets,
+// selections included like the LCU does
+fn update<T>(
+    state: &Mutex<MockState>,
+    subscribers: &Subscribers,
+    f: impl FnOnce(&mut MockState) -> T,
+) -> T {
+    let (result, events) = {
+        let mut state = state.lock().unwrap_or_else(|e| e.into_inner());
+        let (session, phase) =
+            (state.session.clone(), state.gameflow_phase.clone());
+        let result = f(&mut state);
+
+        let mut events = Vec::new();
+        if state.gameflow_phase != phase {
+            events.push(event(
+                GAMEFLOW_PHASE_URI,
+                "Update",
+                json!(state.gameflow_phase),
+            ));
+        }
+        if state.session != session {
+            events.push(match (&session, &state.session) {
+                (_, None) => {
+                    event(CHAMP_SELECT_SESSION_URI, "Delete", Value::Null)
+                }
+                (None, Some(new)) => {
+                    event(CHAMP_SELECT_SESSION_URI, "Create", new.clone())
+                }
+                (Some(_), Some(new)) => {
+                    event(CHAMP_SELECT_SESSION_URI, "Update", new.clone())
+                }
+            });
+        }
+        (result, events)
+    };
+
+    let mut subscribers = subscribers.lock().unwrap_or_else(|e| e.into_inner());
+    for event in events {
+        subscribers.retain(|subscriber| subscriber.send(event.clone()).is_ok());
+    }
+    result
+}
+
+// Subscriptions aren't tracked, every socket gets every event
+fn accept_websocket(request: Request, key: &str, subscribers: &Subscribers) {
+    let mut response = Response::empty(101);
+    if let Ok(header) = Header::from_bytes(
+        "Sec-WebSocket-Accept",
+        derive_accept_key(key.as_bytes()),
+    ) {
+        response.add_header(header);
+    }
+    let stream = request.upgrade("websocket", response);
+
+    let (tx, rx) = mpsc::channel::<String>();
+    subscribers
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .push(tx);
+    thread::spawn(move || {
+        let mut socket = WebSocket::from_raw_socket(stream, Role::Server, None);
+        for event in rx {
+            if socket.send(Message::text(event)).is_err() {
+                return;
+            }
+        }
+    });
+}
+
+fn event(uri: &str, event_type: &str, data: Value) -> String {
+    let topic = format!("OnJsonApiEvent{}", uri.replace('/', "_"));
+    let payload = json!({ "data": data, "eventType": event_type, "uri": uri });
+    json!([WAMP_EVENT, topic, payload]).to_string()
+}
+
+// JSON merge patch, null removes a field
+fn merge(target: &mut Value, patch: &Value) {
+    let (Some(target), Some(patch)) =
+        (target.as_object_mut(), patch.as_object())
+    else {
+        *target = patch.clone();
+        return;
+    };
+    for (key, value) in patch {
+        if value.is_null() {
+            target.remove(key);
+        } else {
+            merge(target.entry(key.clone()).or_insert(Value::Null), value);
+        }
+    }
+}
+
+pub fn route(
+    state: &mut MockState,
+    method: &Method,
+    url: &str,
+    body: &str,
+) -> (u16, String) {
+    let path = url.split('?').next().unwrap_or_default();
+    if let Some(response) = state.responses.get(path) {
+        return (response.status, response.body.clone());
+    }
+    let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+
+    match (method, segments.as_slice()) {
+        (Method::Get, ["help"]) => ok(json!({})),
+        (Method::Get, ["product-session", "v1", "external-sessions"]) => {
+            match &state.product_sessions {
+                Value::Null => ok(json!({})),
+                sessions => ok(sessions.clone()),
+            }
+        }
+        (Method::Get, ["lol-summoner", "v1", "current-summoner"]) => {
+            ok(state.summoner.clone())
+        }
+        (
+            Method::Get,
+            ["lol-summoner", "v1", "current-summoner", "account-and-summoner-ids"],
+        ) => ok(state.summoner.clone()),
+        (Method::Get, ["lol-gameflow", "v1", "gameflow-phase"]) => {
+            ok(json!(state.gameflow_phase))
+        }
+        (
+            Method::Get,
+            ["lol-champions", "v1", "inventories", _, "champions", champion_id, "skins"],
+        ) => champion_id
+            .parse()
+            .ok()
+            .and_then(|id: i64| state.inventories.get(&id))
+            .map(|skins| ok(skins.clone()))
+            .unwrap_or_else(|| not_found(path)),
+        (_, ["lol-champ-select", ..]) if state.session.is_none() => {
+            lcu_error(404, "RPC_ERROR", "No active delegate")
+        }
+        (Method::Get, ["lol-champ-select", "v1", "session"]) => {
+            ok(state.session.clone().unwrap_or_default())
+        }
+        (Method::Get, ["lol-champ-select", "v1", "pickable-skin-ids"]) => {
+            ok(json!(state.pickable_skin_ids))
+        }
+        (Method::Get, ["lol-champ-select", "v1", "current-champion"]) => {
+            ok(json!(state.current_champion))
+        }
+        (
+            Method::Patch,
+            ["lol-champ-select", "v1", "session", "my-selection"],
+        ) => select(state, body),
+        _ => not_found(path),
+    }
+}
+
+// Records the selection and applies it to the local player like the LCU does
+fn select(state: &mut MockState, body: &str) -> (u16, String) {
+    let Ok(selection) = serde_json::from_str::<Value>(body) else {
+        return lcu_error(400, "RPC_ERROR", "Invalid request body");
+    };
+
+    if let (Some(session), Some(skin_id)) =
+        (state.session.as_mut(), selection.get("selectedSkinId"))
+    {
+        let local_cell_id = session["localPlayerCellId"].clone();
+        if let Some(player) =
+            session["myTeam"].as_array_mut().and_then(|team| {
+                team.iter_mut().find(|p| p["cellId"] == local_cell_id)
+            })
+        {
+            player["selectedSkinId"] = skin_id.clone();
+        }
+    }
+
+    state.selections.push(selection);
+    (204, String::new())
+}
+
+fn ok(body: Value) -> (u16, String) {
+    (200, body.to_string())
+}
+
+fn not_found(path: &str) -> (u16, String) {
+    lcu_error(404, "RESOURCE_NOT_FOUND", &format!("Invalid URI {}", path))
+}
+
+// Same payload shape the LCU uses for its errors
+fn lcu_error(status: u16, error_code: &str, message: &str) -> (u16, String) {
+    let body = json!({
+        "errorCode": error_code,
+        "httpStatus": status,
+        "implementationDetails": {},
+        "message": message,
+    });
+    (status, body.to_string())
+}
@@ -0,0 +1,41 @@
+use mock_lcu::{MockLcu, MockResult, MockState, TimelineStep};
+
+use std::env;
+use std::fs;
+use std::thread;
+use std::time::Duration;
+
+// Usage: mock-lcu [state.json] [timeline.json], the token is taken from
+// LCU_TOKEN if set
+fn main() -> MockResult<()> {
+    let state: MockState = match env::args().nth(1) {
+        Some(path) => serde_json::from_str(&fs::read_to_string(path)?)?,
+        None => MockState::default(),
+    };
+    let timeline: Vec<TimelineStep> = match env::args().nth(2) {
+        Some(path) => serde_json::from_str(&fs::read_to_string(path)?)?,
+        None => Vec::new(),
+    };
+    let token = env::var("LCU_TOKEN").unwrap_or_else(|_| "mock-token".into());
+
+    let mock = MockLcu::start(&token, state)?;
+    println!("Mock LCU listening on https://127.0.0.1:{}", mock.port);
+    println!(
+        "Run the randomizer with --lcu-port {} --lcu-token {} and insecure_tls",
+        mock.port, mock.token
+    );
+
+    let mut seen = 0;
+    let mut timeline = timeline.iter();
+    loop {
+        match timeline.next() {
+            Some(step) => mock.apply(step),
+            None => thread::sleep(Duration::from_millis(250)),
+        }
+        let selections = mock.selections();
+        for selection in &selections[seen..] {
+            println!("my-selection {}", selection);
+        }
+        seen = selections.len();
+    }
+}
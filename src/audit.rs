@@ -0,0 +1,33 @@
+use serde::Serialize;
+use serde_json::Value;
+
+use std::error::Error as StdError;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::Path;
+
+// One line of the audit log, written for every mutating request attempted
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct AuditEntry {
+    pub time_ms: i64,
+    pub port: String,
+    pub method: String,
+    pub path: String,
+    pub body: Option<Value>,
+    // What the guard saw, None when the session couldn't be read
+    pub queue_id: Option<i64>,
+    pub is_custom_game: Option<bool>,
+    pub phase: Option<String>,
+    // "sent", or why the request was blocked or failed
+    pub outcome: String,
+}
+
+// JSON lines, appended so several clients can share the file
+pub fn append(
+    path: &Path,
+    entry: &AuditEntry,
+) -> Result<(), Box<dyn StdError>> {
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(file, "{}", serde_json::to_string(entry)?)?;
+    Ok(())
+}
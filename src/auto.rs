@@ -0,0 +1,192 @@
+use serde::{Deserialize, Serialize};
+
+use std::collections::HashMap;
+
+use crate::config::Config;
+use crate::models::{ChampSelectSession, Player};
+use crate::scheduler::{Scheduler, TooLate};
+
+// Timer phases in which the skin can still be changed
+const SKIN_SELECT_PHASES: [&str; 2] = ["BAN_PICK", "FINALIZATION"];
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AutoMode {
+    #[default]
+    Off,
+    // Like the skin button
+    Skin,
+    // Like the both button, skin and chroma in one selection
+    Roll,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AutoTiming {
+    // As soon as the pick is locked in
+    #[default]
+    Locked,
+    // Just before finalization ends, see finalization_lead_secs
+    Finalization,
+}
+
+// A randomization come due, for the client whose session asked for it
+#[derive(Debug, Clone, PartialEq)]
+pub struct Due {
+    pub port: String,
+    pub mode: AutoMode,
+}
+
+// Decides from champ select session updates when to randomize on its own
+#[derive(Debug, Clone, Default)]
+pub struct AutoRandomizer {
+    mode: AutoMode,
+    queues: HashMap<i64, AutoMode>,
+    timing: AutoTiming,
+    scheduler: Scheduler,
+    // Client and session the fields below belong to
+    port: String,
+    game_id: Option<i64>,
+    // My locked champion and its skin as of the last update
+    champion_id: Option<i64>,
+    skin_id: i64,
+    // The next skin change is our own selection arriving
+    pending: bool,
+    // Champions the skin was changed for by hand, never randomized again
+    manual: Vec<i64>,
+}
+
+impl AutoRandomizer {
+    pub fn new(config: &Config) -> Self {
+        Self {
+            mode: config.auto_randomize,
+            queues: config.auto_randomize_queues.clone(),
+            timing: config.auto_randomize_timing,
+            scheduler: Scheduler::new(
+                config.finalization_lead(),
+                config.latency_margin(),
+            ),
+            ..Default::default()
+        }
+    }
+
+    pub fn mode(&self, queue_id: i64) -> AutoMode {
+        self.queues.get(&queue_id).copied().unwrap_or(self.mode)
+    }
+
+    fn reset(&mut self, port: &str, game_id: Option<i64>) {
+        self.port = port.to_string();
+        self.game_id = game_id;
+        self.champion_id = None;
+        self.skin_id = 0;
+        self.pending = false;
+        self.manual.clear();
+        self.scheduler.cancel();
+    }
+
+    // Fires once the local pick is locked in and again whenever a trade,
+    // reroll or bench swap brings another champion, None passes the session
+    // ending. With finalization timing it is only armed here, poll fires it
+    pub fn on_session(
+        &mut self,
+        port: &str,
+        session: Option<&ChampSelectSession>,
+        now_ms: i64,
+    ) -> Option<Result<Due, TooLate>> {
+        let Some(session) = session else {
+            self.reset(port, None);
+            return None;
+        };
+        if self.port != port || self.game_id != Some(session.game_id) {
+            self.reset(port, Some(session.game_id));
+        }
+        self.scheduler.on_timer(&session.timer, now_ms);
+
+        let mode = self.mode(session.queue_id);
+        if mode == AutoMode::Off
+            || session.is_spectating
+            || !session.allow_skin_selection
+            || !SKIN_SELECT_PHASES.contains(&session.timer.phase.as_str())
+        {
+            return None;
+        }
+
+        let champion_id = locked_champion(session)?;
+        let skin_id = local_player(session)?.selected_skin_id;
+        if self.champion_id == Some(champion_id) {
+            // Trades and swaps update the session several times, only a
+            // different champion counts
+            if skin_id != self.skin_id {
+                self.skin_id = skin_id;
+                if !std::mem::take(&mut self.pending) {
+                    self.manual.push(champion_id);
+                    self.scheduler.cancel();
+                }
+            }
+            return self.poll(now_ms);
+        }
+
+        self.champion_id = Some(champion_id);
+        self.skin_id = skin_id;
+        self.pending = false;
+        if self.manual.contains(&champion_id) {
+            self.scheduler.cancel();
+            return None;
+        }
+        match self.timing {
+            AutoTiming::Locked => self.fire(mode),
+            AutoTiming::Finalization => {
+                self.scheduler.arm(mode);
+                self.poll(now_ms)
+            }
+        }
+    }
+
+    // Called regularly, timed randomizations fire from here or are dropped
+    // when they could no longer land
+    pub fn poll(&mut self, now_ms: i64) -> Option<Result<Due, TooLate>> {
+        match self.scheduler.poll(now_ms)? {
+            Ok(mode) => self.fire(mode),
+            Err(too_late) => Some(Err(too_late)),
+        }
+    }
+
+    // The skin a fired randomization selected, None when it failed or was
+    // blocked. Only a different skin brings the update that clears pending,
+    // otherwise the next change would pass for ours
+    pub fn on_randomized(&mut self, port: &str, selected: Option<i64>) {
+        if port != self.port {
+            return;
+        }
+        if selected.is_none_or(|skin_id| skin_id == self.skin_id) {
+            self.pending = false;
+        }
+    }
+
+    fn fire(&mut self, mode: AutoMode) -> Option<Result<Due, TooLate>> {
+        self.pending = true;
+        Some(Ok(Due {
+            port: self.port.clone(),
+            mode,
+        }))
+    }
+}
+
+pub fn local_player(session: &ChampSelectSession) -> Option<&Player> {
+    let cell_id = session.local_player_cell_id;
+    session.my_team.iter().find(|p| p.cell_id == cell_id)
+}
+
+// The local player's champion once every pick action of theirs completed,
+// modes without picks (e.g. ARAM) lock as soon as one is assigned
+pub fn locked_champion(session: &ChampSelectSession) -> Option<i64> {
+    let cell_id = session.local_player_cell_id;
+    let me = local_player(session)?;
+    let locked = session
+        .actions
+        .iter()
+        .flatten()
+        .filter(|a| a.actor_cell_id == cell_id && a.action_type == "pick")
+        .all(|a| a.completed);
+    (locked && me.champion_id != 0).then_some(me.champion_id)
+}
@@ -0,0 +1,222 @@
+use serde::{Deserialize, Serialize};
+
+use crate::auto::{AutoMode, AutoTiming};
+use crate::selection::{ChromaMode, RarityWeights, SkinMode};
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+const CONFIG_FILE: &str = "config.json";
+const SHUFFLE_BAGS_FILE: &str = "shuffle_bags.json";
+const SKIN_FILTERS_FILE: &str = "skin_filters.json";
+const PLAY_HISTORY_FILE: &str = "play_history.json";
+const AUDIT_LOG_FILE: &str = "audit.log";
+const SECS_PER_DAY: f64 = 24.0 * 60.0 * 60.0;
+// Draft and blind pick, ARAM, swiftplay, quickplay, co-op vs AI, URF, Arena.
+// Ranked solo/duo (420), flex (440) and Clash (700) are left out
+const DEFAULT_GUARD_QUEUES: [i64; 11] =
+    [400, 430, 450, 480, 490, 870, 880, 890, 900, 1700, 1900];
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SourceKind {
+    Cli,
+    Env,
+    Lockfile,
+    Process,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub install_dir: PathBuf,
+    pub credential_sources: Vec<SourceKind>,
+    // Lists every running client instead of the first one found, e.g. for
+    // several accounts at once
+    pub multi_client: bool,
+    // Defaults to the Riot Client's lockfile under %LOCALAPPDATA%
+    pub riot_client_lockfile: Option<PathBuf>,
+    // Skips verifying the LCU certificate against the Riot root CA
+    pub insecure_tls: bool,
+    // PEM file of a CA pinned instead of the bundled Riot root, e.g. the
+    // mock's
+    pub root_ca: Option<PathBuf>,
+    // Applied to every LCU request so a hung client can't freeze the app
+    pub connect_timeout_ms: u64,
+    pub request_timeout_ms: u64,
+    pub skin_mode: SkinMode,
+    // Shared by a group of friends for the same skin of the day
+    pub group_phrase: String,
+    // Time for a played skin to get half its odds back in least_recent
+    pub lru_half_life_days: f64,
+    // Relative odds per skin rarity, e.g. {"ultimate": 4, "base": 0.5}
+    pub rarity_weights: RarityWeights,
+    // How the combined roll treats chromas
+    pub chroma_mode: ChromaMode,
+    // Odds of a chroma over the plain skin in the second stage
+    pub chroma_chance: f64,
+    // Randomizes by itself once the pick is locked in
+    pub auto_randomize: AutoMode,
+    // Overrides auto_randomize by queue id, e.g. {"420": "off"}
+    pub auto_randomize_queues: HashMap<i64, AutoMode>,
+    pub auto_randomize_timing: AutoTiming,
+    // How long before finalization ends the timed randomization lands
+    pub finalization_lead_secs: f64,
+    // Added on top for the selection request to get through
+    pub latency_margin_ms: u64,
+    // Skin selections are only sent in these queues, every queue if None
+    pub guard_queues: Option<Vec<i64>>,
+    pub guard_custom_games: bool,
+    // Champ select timer phases skin selections are sent in
+    pub guard_phases: Vec<String>,
+    // Saves every LCU request and response, token redacted, to this file
+    pub record_fixture: Option<PathBuf>,
+    // Answers requests from a recorded fixture instead of a live client
+    pub replay_fixture: Option<PathBuf>,
+    // Where shuffle bags, filters, history and the audit log are kept, next
+    // to the executable if None
+    pub data_dir: Option<PathBuf>,
+    #[serde(skip)]
+    pub cli_port: Option<String>,
+    #[serde(skip)]
+    pub cli_token: Option<String>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            install_dir: PathBuf::from(r"C:\Riot Games\League of Legends"),
+            credential_sources: vec![
+                SourceKind::Cli,
+                SourceKind::Env,
+                SourceKind::Lockfile,
+                SourceKind::Process,
+            ],
+            multi_client: false,
+            riot_client_lockfile: None,
+            insecure_tls: false,
+            root_ca: None,
+            connect_timeout_ms: 2000,
+            request_timeout_ms: 5000,
+            skin_mode: SkinMode::default(),
+            group_phrase: String::new(),
+            lru_half_life_days: 7.0,
+            rarity_weights: RarityWeights::default(),
+            chroma_mode: ChromaMode::default(),
+            chroma_chance: 0.5,
+            auto_randomize: AutoMode::default(),
+            auto_randomize_queues: HashMap::new(),
+            auto_randomize_timing: AutoTiming::default(),
+            finalization_lead_secs: 3.0,
+            latency_margin_ms: 500,
+            guard_queues: Some(DEFAULT_GUARD_QUEUES.to_vec()),
+            guard_custom_games: true,
+            guard_phases: vec![
+                "BAN_PICK".to_string(),
+                "FINALIZATION".to_string(),
+            ],
+            record_fixture: None,
+            replay_fixture: None,
+            data_dir: None,
+            cli_port: None,
+            cli_token: None,
+        }
+    }
+}
+
+impl Config {
+    pub fn load() -> Self {
+        let path = data_dir().join(CONFIG_FILE);
+        let Ok(config_str) = fs::read_to_string(path) else {
+            return Self::default();
+        };
+        serde_json::from_str(&config_str).unwrap_or_else(|e| {
+            dbg!(e);
+            Self::default()
+        })
+    }
+
+    pub fn riot_client_lockfile_path(&self) -> Option<PathBuf> {
+        self.riot_client_lockfile.clone().or_else(|| {
+            let local_app_data = std::env::var_os("LOCALAPPDATA")?;
+            Some(
+                PathBuf::from(local_app_data)
+                    .join("Riot Games")
+                    .join("Riot Client")
+                    .join("Config")
+                    .join("lockfile"),
+            )
+        })
+    }
+
+    fn state_dir(&self) -> PathBuf {
+        self.data_dir.clone().unwrap_or_else(data_dir)
+    }
+
+    pub fn shuffle_bags_path(&self) -> PathBuf {
+        self.state_dir().join(SHUFFLE_BAGS_FILE)
+    }
+
+    pub fn skin_filters_path(&self) -> PathBuf {
+        self.state_dir().join(SKIN_FILTERS_FILE)
+    }
+
+    pub fn play_history_path(&self) -> PathBuf {
+        self.state_dir().join(PLAY_HISTORY_FILE)
+    }
+
+    pub fn audit_log_path(&self) -> PathBuf {
+        self.state_dir().join(AUDIT_LOG_FILE)
+    }
+
+    pub fn lru_half_life(&self) -> Duration {
+        Duration::try_from_secs_f64(self.lru_half_life_days * SECS_PER_DAY)
+            .unwrap_or_default()
+    }
+
+    pub fn finalization_lead(&self) -> Duration {
+        Duration::try_from_secs_f64(self.finalization_lead_secs)
+            .unwrap_or_default()
+    }
+
+    pub fn latency_margin(&self) -> Duration {
+        Duration::from_millis(self.latency_margin_ms)
+    }
+
+    pub fn connect_timeout(&self) -> Duration {
+        Duration::from_millis(self.connect_timeout_ms)
+    }
+
+    pub fn request_timeout(&self) -> Duration {
+        Duration::from_millis(self.request_timeout_ms)
+    }
+
+    // Accepts both --flag value and --flag=value
+    pub fn apply_args(&mut self, args: impl IntoIterator<Item = String>) {
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            let (flag, mut inline_value) = match arg.split_once('=') {
+                Some((flag, value)) => (flag.to_string(), Some(value.into())),
+                None => (arg, None),
+            };
+            let mut value = || inline_value.take().or_else(|| args.next());
+            match flag.as_str() {
+                "--lcu-port" => self.cli_port = value(),
+                "--lcu-token" => self.cli_token = value(),
+                "--record" => self.record_fixture = value().map(PathBuf::from),
+                "--replay" => self.replay_fixture = value().map(PathBuf::from),
+                _ => {}
+            }
+        }
+    }
+}
+
+// Config and persisted state live next to the executable (portable app)
+pub fn data_dir() -> PathBuf {
+    std::env::current_exe()
+        .ok()
+        .and_then(|exe| exe.parent().map(Path::to_path_buf))
+        .unwrap_or_default()
+}
@@ -0,0 +1,204 @@
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+
+use crate::discovery::PortAndToken;
+use crate::events::{self, LcuEvent};
+use crate::lcu::ClientHealth;
+use crate::registry::{ClientInfo, ClientRegistry};
+
+const BACKOFF_INITIAL: Duration = Duration::from_secs(1);
+const BACKOFF_MAX: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConnectionState {
+    Disconnected,
+    Discovering,
+    Authenticating,
+    Connected(ClientHealth),
+    Failed(String),
+}
+
+#[derive(Debug, Clone)]
+pub struct Backoff {
+    initial: Duration,
+    max: Duration,
+    failures: u32,
+}
+
+impl Default for Backoff {
+    fn default() -> Self {
+        Self::new(BACKOFF_INITIAL, BACKOFF_MAX)
+    }
+}
+
+impl Backoff {
+    pub fn new(initial: Duration, max: Duration) -> Self {
+        Self {
+            initial,
+            max,
+            failures: 0,
+        }
+    }
+
+    // Doubles with every consecutive failure, up to the cap
+    pub fn delay(&self) -> Duration {
+        let factor = 2u32.saturating_pow(self.failures.saturating_sub(1));
+        self.initial.saturating_mul(factor).min(self.max)
+    }
+
+    fn fail(&mut self) {
+        self.failures = self.failures.saturating_add(1);
+    }
+
+    fn reset(&mut self) {
+        self.failures = 0;
+    }
+}
+
+// Pure state machine, the driver below feeds it the outcome of each step
+#[derive(Debug, Clone)]
+pub struct Connection {
+    state: ConnectionState,
+    backoff: Backoff,
+    credentials: Option<PortAndToken>,
+}
+
+impl Connection {
+    pub fn new(backoff: Backoff) -> Self {
+        Self {
+            state: ConnectionState::Disconnected,
+            backoff,
+            credentials: None,
+        }
+    }
+
+    // Wait before the next discovery, only failures back off
+    pub fn next_attempt_in(&self) -> Duration {
+        match self.state {
+            ConnectionState::Failed(_) => self.backoff.delay(),
+            _ => Duration::ZERO,
+        }
+    }
+
+    // True when the client came back on another port or with another token
+    pub fn credentials_changed(&self, credentials: &PortAndToken) -> bool {
+        self.credentials
+            .as_ref()
+            .is_some_and(|known| known != credentials)
+    }
+
+    // Every transition returns the new state only if it actually changed
+    pub fn discovering(&mut self) -> Option<ConnectionState> {
+        self.transition(ConnectionState::Discovering)
+    }
+
+    pub fn discovered(
+        &mut self,
+        credentials: PortAndToken,
+    ) -> Option<ConnectionState> {
+        self.credentials = Some(credentials);
+        self.transition(ConnectionState::Authenticating)
+    }
+
+    pub fn connected(
+        &mut self,
+        health: ClientHealth,
+    ) -> Option<ConnectionState> {
+        self.backoff.reset();
+        self.transition(ConnectionState::Connected(health))
+    }
+
+    pub fn failed(&mut self, reason: String) -> Option<ConnectionState> {
+        self.backoff.fail();
+        self.transition(ConnectionState::Failed(reason))
+    }
+
+    pub fn lost(&mut self) -> Option<ConnectionState> {
+        self.transition(ConnectionState::Disconnected)
+    }
+
+    fn transition(&mut self, to: ConnectionState) -> Option<ConnectionState> {
+        if self.state == to {
+            return None;
+        }
+        self.state = to.clone();
+        Some(to)
+    }
+}
+
+// Keeps the primary client connected for as long as the app runs,
+// forwarding its events and every refreshed client list
+pub fn run(
+    registry: &Mutex<ClientRegistry>,
+    backoff: Backoff,
+    mut on_state: impl FnMut(ConnectionState),
+    mut on_clients: impl FnMut(Vec<ClientInfo>),
+    // Called with the port of the client the event came from
+    mut on_event: impl FnMut(&str, LcuEvent),
+) {
+    let mut connection = Connection::new(backoff);
+    let mut emit = |state: Option<ConnectionState>| {
+        if let Some(state) = state {
+            on_state(state);
+        }
+    };
+
+    loop {
+        thread::sleep(connection.next_attempt_in());
+        emit(connection.discovering());
+
+        let refreshed = ClientRegistry::refresh_shared(registry);
+        // The lock is only held to copy the primary out, the buttons would
+        // wait on the health check and subscription otherwise
+        let Ok(guard) = registry.lock() else {
+            return;
+        };
+        on_clients(guard.list());
+        let primary = guard.primary().cloned();
+        drop(guard);
+        let primary = match refreshed.map(|_| primary) {
+            Ok(Some(primary)) => primary,
+            Ok(None) => {
+                emit(connection.failed("No client found".to_string()));
+                continue;
+            }
+            Err(e) => {
+                emit(connection.failed(e.to_string()));
+                continue;
+            }
+        };
+        let credentials = primary.credentials.clone();
+        if connection.credentials_changed(&credentials) {
+            println!(
+                "LeagueClient restarted, reconnecting with new credentials"
+            );
+        }
+        emit(connection.discovered(credentials));
+
+        let connected = match primary.client.health() {
+            Ok(health) => primary
+                .client
+                .subscribe_events()
+                .map(|socket| (health, socket)),
+            Err(e) => Err(e.into()),
+        };
+
+        let (health, mut socket) = match connected {
+            Ok(connected) => connected,
+            Err(e) => {
+                emit(connection.failed(e.to_string()));
+                continue;
+            }
+        };
+        emit(connection.connected(health));
+
+        let port = &primary.info.port;
+        if let Err(e) =
+            events::listen(&mut socket, |event| on_event(port, event))
+        {
+            println!("LCU connection lost: {}", e);
+        }
+        emit(connection.lost());
+    }
+}
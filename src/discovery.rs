@@ -0,0 +1,780 @@
+#[cfg(windows)]
+use regex::Regex;
+
+use std::env;
+use std::error::Error as StdError;
+use std::fmt;
+use std::fs;
+#[cfg(windows)]
+use std::os::windows::process::CommandExt;
+use std::path::{Path, PathBuf};
+#[cfg(windows)]
+use std::process::Command;
+
+use crate::config::{Config, SourceKind};
+
+const LOCKFILE_NAME: &str = "lockfile";
+const ENV_PORT: &str = "LCU_PORT";
+const ENV_TOKEN: &str = "LCU_TOKEN";
+const CLIENT_UX_EXE: &str = "LeagueClientUx.exe";
+#[cfg(unix)]
+const PROC_ROOT: &str = "/proc";
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PortAndToken {
+    pub port: String,
+    pub auth_token: String,
+    // Riot Client api the LeagueClientUx was launched against, if known
+    pub riot_client: Option<Box<PortAndToken>>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Lockfile {
+    pub name: String,
+    pub pid: u32,
+    pub port: String,
+    pub password: String,
+    pub protocol: String,
+}
+
+impl From<Lockfile> for PortAndToken {
+    fn from(lockfile: Lockfile) -> Self {
+        Self {
+            port: lockfile.port,
+            auth_token: lockfile.password,
+            riot_client: None,
+        }
+    }
+}
+
+pub trait CredentialSource: fmt::Debug + Send + Sync {
+    fn name(&self) -> &'static str;
+    fn discover(&self) -> Result<PortAndToken, Box<dyn StdError>>;
+
+    // Sources that can see several clients at once override these
+    fn discover_all(&self) -> Result<Vec<PortAndToken>, Box<dyn StdError>> {
+        Ok(vec![self.discover()?])
+    }
+
+    fn sees_every_client(&self) -> bool {
+        false
+    }
+
+    // Credentials given by hand, nothing else is looked for once they answer
+    fn is_explicit(&self) -> bool {
+        false
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct CliSource {
+    pub port: Option<String>,
+    pub auth_token: Option<String>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct EnvSource;
+
+#[derive(Debug, Clone, Default)]
+pub struct LockfileSource {
+    pub install_dir: PathBuf,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ProcessSource;
+
+impl CredentialSource for CliSource {
+    fn name(&self) -> &'static str {
+        "cli"
+    }
+
+    fn discover(&self) -> Result<PortAndToken, Box<dyn StdError>> {
+        let (Some(port), Some(auth_token)) = (&self.port, &self.auth_token)
+        else {
+            return Err("--lcu-port and --lcu-token not given".into());
+        };
+        Ok(PortAndToken {
+            port: port.clone(),
+            auth_token: auth_token.clone(),
+            riot_client: None,
+        })
+    }
+
+    fn is_explicit(&self) -> bool {
+        true
+    }
+}
+
+impl CredentialSource for EnvSource {
+    fn name(&self) -> &'static str {
+        "env"
+    }
+
+    fn discover(&self) -> Result<PortAndToken, Box<dyn StdError>> {
+        let port = env::var(ENV_PORT).map_err(|_| "LCU_PORT not set")?;
+        let auth_token =
+            env::var(ENV_TOKEN).map_err(|_| "LCU_TOKEN not set")?;
+        Ok(PortAndToken {
+            port,
+            auth_token,
+            riot_client: None,
+        })
+    }
+
+    fn is_explicit(&self) -> bool {
+        true
+    }
+}
+
+impl CredentialSource for LockfileSource {
+    fn name(&self) -> &'static str {
+        "lockfile"
+    }
+
+    fn discover(&self) -> Result<PortAndToken, Box<dyn StdError>> {
+        let lockfile_path = self.install_dir.join(LOCKFILE_NAME);
+        read_lockfile(&lockfile_path, process_alive)?
+            .map(PortAndToken::from)
+            .ok_or_else(|| "No lockfile found".into())
+    }
+}
+
+impl CredentialSource for ProcessSource {
+    fn name(&self) -> &'static str {
+        "process"
+    }
+
+    fn discover(&self) -> Result<PortAndToken, Box<dyn StdError>> {
+        Ok(process_scan()?.swap_remove(0))
+    }
+
+    fn discover_all(&self) -> Result<Vec<PortAndToken>, Box<dyn StdError>> {
+        process_scan()
+    }
+
+    fn sees_every_client(&self) -> bool {
+        true
+    }
+}
+
+// The Riot Client writes its own lockfile, also while League isn't launched
+pub fn read_riot_client_lockfile(
+    path: &Path,
+) -> Result<Option<PortAndToken>, Box<dyn StdError>> {
+    Ok(read_lockfile(path, process_alive)?.map(PortAndToken::from))
+}
+
+pub fn sources_from_config(config: &Config) -> Vec<Box<dyn CredentialSource>> {
+    config
+        .credential_sources
+        .iter()
+        .map(|kind| -> Box<dyn CredentialSource> {
+            match kind {
+                SourceKind::Cli => Box::new(CliSource {
+                    port: config.cli_port.clone(),
+                    auth_token: config.cli_token.clone(),
+                }),
+                SourceKind::Env => Box::new(EnvSource),
+                SourceKind::Lockfile => Box::new(LockfileSource {
+                    install_dir: config.install_dir.clone(),
+                }),
+                SourceKind::Process => Box::new(ProcessSource),
+            }
+        })
+        .collect()
+}
+
+// The first source that answers wins, like with a single client. With
+// multi_client the sources seeing every client are still asked for the
+// others, unless the answer was given by hand. A port found by several
+// sources is credited to the first of them
+pub fn discover_all(
+    sources: &[Box<dyn CredentialSource>],
+    multi_client: bool,
+) -> Result<Vec<(&'static str, PortAndToken)>, Box<dyn StdError>> {
+    let mut found: Vec<(&'static str, PortAndToken)> = Vec::new();
+    let mut errors = Vec::new();
+    for source in sources {
+        let wanted =
+            found.is_empty() || multi_client && source.sees_every_client();
+        if !wanted {
+            continue;
+        }
+        match source.discover_all() {
+            Ok(clients) => {
+                for port_and_token in clients {
+                    if !found.iter().any(|(_, c)| c.port == port_and_token.port)
+                    {
+                        found.push((source.name(), port_and_token));
+                    }
+                }
+                if source.is_explicit() {
+                    break;
+                }
+            }
+            Err(e) => errors.push(format!("{}: {}", source.name(), e)),
+        }
+    }
+    if found.is_empty() {
+        return Err(
+            format!("No credentials found ({})", errors.join(", ")).into()
+        );
+    }
+    Ok(found)
+}
+
+#[cfg(windows)]
+fn process_scan() -> Result<Vec<PortAndToken>, Box<dyn StdError>> {
+    build_wmic_wmi()
+}
+
+#[cfg(unix)]
+fn process_scan() -> Result<Vec<PortAndToken>, Box<dyn StdError>> {
+    scan_procfs(Path::new(PROC_ROOT))
+}
+
+// Format is name:pid:port:password:protocol
+pub fn parse_lockfile(contents: &str) -> Result<Lockfile, Box<dyn StdError>> {
+    let fields: Vec<&str> = contents.trim().split(':').collect();
+    let [name, pid, port, password, protocol] = fields[..] else {
+        return Err("Malformed lockfile".into());
+    };
+
+    if port.is_empty() || port.parse::<u16>().is_err() {
+        return Err("Invalid port in lockfile".into());
+    }
+    if password.is_empty() {
+        return Err("Auth token not found in lockfile".into());
+    }
+
+    Ok(Lockfile {
+        name: name.to_string(),
+        pid: pid.parse()?,
+        port: port.to_string(),
+        password: password.to_string(),
+        protocol: protocol.to_string(),
+    })
+}
+
+// Ok(None) when there is no lockfile, a stale one is an error
+pub fn read_lockfile(
+    path: &Path,
+    is_alive: impl Fn(u32) -> bool,
+) -> Result<Option<Lockfile>, Box<dyn StdError>> {
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+
+    let lockfile = parse_lockfile(&contents)?;
+    if !is_alive(lockfile.pid) {
+        return Err("Stale lockfile, client process not running".into());
+    }
+    Ok(Some(lockfile))
+}
+
+#[cfg(windows)]
+fn process_alive(pid: u32) -> bool {
+    use windows_sys::Win32::Foundation::{CloseHandle, STILL_ACTIVE};
+    use windows_sys::Win32::System::Threading::{
+        GetExitCodeProcess, OpenProcess, PROCESS_QUERY_LIMITED_INFORMATION,
+    };
+
+    unsafe {
+        let handle = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, 0, pid);
+        if handle.is_null() {
+            return false;
+        }
+        let mut exit_code = 0u32;
+        let res = GetExitCodeProcess(handle, &mut exit_code);
+        CloseHandle(handle);
+        res != 0 && exit_code == STILL_ACTIVE as u32
+    }
+}
+
+// Wine pids don't map to host pids, so on unix the lockfile is taken as is
+// and a stale one just fails to connect
+#[cfg(unix)]
+fn process_alive(_pid: u32) -> bool {
+    true
+}
+
+// Takes the procfs root as a parameter so it can be pointed at a fake tree
+#[cfg(unix)]
+pub fn scan_procfs(
+    proc_root: &Path,
+) -> Result<Vec<PortAndToken>, Box<dyn StdError>> {
+    let mut clients = Vec::new();
+    for entry in fs::read_dir(proc_root)?.flatten() {
+        let is_pid = entry
+            .file_name()
+            .to_str()
+            .is_some_and(|name| name.chars().all(|c| c.is_ascii_digit()));
+        if !is_pid {
+            continue;
+        }
+
+        // Processes can exit or deny access while we iterate
+        let Ok(cmdline) = fs::read(entry.path().join("cmdline")) else {
+            continue;
+        };
+        if let Some(port_and_token) = parse_cmdline(&cmdline) {
+            clients.push(port_and_token);
+        }
+    }
+
+    if clients.is_empty() {
+        return Err("LeagueClientUx process not found".into());
+    }
+    Ok(clients)
+}
+
+// Arguments in /proc/<pid>/cmdline are NUL separated
+pub fn parse_cmdline(cmdline: &[u8]) -> Option<PortAndToken> {
+    let args: Vec<String> = cmdline
+        .split(|b| *b == 0)
+        .map(|arg| String::from_utf8_lossy(arg).to_string())
+        .collect();
+
+    let client_ux = CLIENT_UX_EXE.to_ascii_lowercase();
+    if !args
+        .iter()
+        .any(|arg| arg.to_ascii_lowercase().ends_with(&client_ux))
+    {
+        return None;
+    }
+
+    let find_arg = |prefix: &str| {
+        args.iter()
+            .find_map(|arg| arg.strip_prefix(prefix))
+            .filter(|value| !value.is_empty())
+            .map(str::to_string)
+    };
+
+    let riot_client = match (
+        find_arg("--riotclientport="),
+        find_arg("--riotclientauthtoken="),
+    ) {
+        (Some(port), Some(auth_token)) => Some(Box::new(PortAndToken {
+            port,
+            auth_token,
+            riot_client: None,
+        })),
+        _ => None,
+    };
+
+    Some(PortAndToken {
+        port: find_arg("--app-port=")?,
+        auth_token: find_arg("--remoting-auth-token=")?,
+        riot_client,
+    })
+}
+
+#[cfg(windows)]
+fn build_wmic_wmi() -> Result<Vec<PortAndToken>, Box<dyn StdError>> {
+    let re_port = Regex::new(r"--app-port=([0-9]+)")?;
+    let re_auth_token = Regex::new(r"--remoting-auth-token=([\w-]+)")?;
+    let re_riot_port = Regex::new(r"--riotclientport=([0-9]+)")?;
+    let re_riot_token = Regex::new(r"--riotclientauthtoken=([\w-]+)")?;
+
+    // Try WMIC first (Windows 10)
+    let wmic_cmd = Command::new("wmic")
+        .args([
+            "PROCESS",
+            "WHERE",
+            "name='LeagueClientUx.exe'",
+            "GET",
+            "commandline",
+        ])
+        .creation_flags(0x08000000)
+        .output();
+
+    let output_string: String;
+    let cmd_output_str: &str = match wmic_cmd {
+        Ok(ref out) if out.status.success() => {
+            output_string = String::from_utf8_lossy(&out.stdout).to_string();
+            &output_string
+        }
+        _ => {
+            // When wmic fails, try PowerShell WMI (Windows 11)
+            let wmi_cmd = r#"Get-CimInstance Win32_Process | Where-Object { $_.Name -eq 'LeagueClientUx.exe' } | Select-Object -ExpandProperty CommandLine"#;
+            let wmi_out = Command::new("powershell")
+                .args(["-Command", wmi_cmd])
+                .creation_flags(0x08000000)
+                .output()?;
+            output_string =
+                String::from_utf8_lossy(&wmi_out.stdout).to_string();
+            &output_string
+        }
+    };
+
+    // One command line per running client
+    let clients: Vec<PortAndToken> = cmd_output_str
+        .lines()
+        .filter_map(|line| {
+            let capture = |re: &Regex| {
+                Some(re.captures(line)?.get(1)?.as_str().to_string())
+            };
+            let riot_client =
+                match (capture(&re_riot_port), capture(&re_riot_token)) {
+                    (Some(port), Some(auth_token)) => {
+                        Some(Box::new(PortAndToken {
+                            port,
+                            auth_token,
+                            riot_client: None,
+                        }))
+                    }
+                    _ => None,
+                };
+            Some(PortAndToken {
+                port: capture(&re_port)?,
+                auth_token: capture(&re_auth_token)?,
+                riot_client,
+            })
+        })
+        .collect();
+
+    if clients.is_empty() {
+        return Err("Port and auth token not found".into());
+    }
+    Ok(clients)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Writes a fake /proc/<pid>/cmdline with NUL separated arguments
+    #[cfg(unix)]
+    fn write_cmdline(proc_root: &Path, pid: &str, args: &[&str]) {
+        let dir = proc_root.join(pid);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("cmdline"), args.join("\0")).unwrap();
+    }
+
+    #[test]
+    fn parses_lockfile() {
+        let lockfile =
+            parse_lockfile("LeagueClient:1234:54321:s3cr3t:https\n").unwrap();
+        assert_eq!(
+            lockfile,
+            Lockfile {
+                name: "LeagueClient".to_string(),
+                pid: 1234,
+                port: "54321".to_string(),
+                password: "s3cr3t".to_string(),
+                protocol: "https".to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_lockfile() {
+        for contents in ["", "LeagueClient:1234:54321:s3cr3t", "a:b:c:d:e:f"] {
+            let err = parse_lockfile(contents).unwrap_err();
+            assert_eq!(err.to_string(), "Malformed lockfile");
+        }
+        assert!(parse_lockfile("LeagueClient:pid:54321:s3cr3t:https").is_err());
+        let err = parse_lockfile("LeagueClient:1234:54321::https").unwrap_err();
+        assert_eq!(err.to_string(), "Auth token not found in lockfile");
+    }
+
+    #[test]
+    fn rejects_bad_port() {
+        for port in ["", "port", "70000", "-1"] {
+            let contents = format!("LeagueClient:1234:{}:s3cr3t:https", port);
+            let err = parse_lockfile(&contents).unwrap_err();
+            assert_eq!(err.to_string(), "Invalid port in lockfile");
+        }
+    }
+
+    #[test]
+    fn reads_lockfile_of_live_process() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(LOCKFILE_NAME);
+        fs::write(&path, "LeagueClient:1234:54321:s3cr3t:https").unwrap();
+
+        let lockfile = read_lockfile(&path, |pid| pid == 1234).unwrap();
+        assert_eq!(lockfile.map(|l| l.port), Some("54321".to_string()));
+    }
+
+    #[test]
+    fn rejects_stale_lockfile() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(LOCKFILE_NAME);
+        fs::write(&path, "LeagueClient:1234:54321:s3cr3t:https").unwrap();
+
+        let err = read_lockfile(&path, |_| false).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Stale lockfile, client process not running"
+        );
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn checks_the_lockfile_pid() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(LOCKFILE_NAME);
+        let write = |pid: u32| {
+            let contents = format!("LeagueClient:{}:54321:s3cr3t:https", pid);
+            fs::write(&path, contents).unwrap();
+        };
+
+        write(std::process::id());
+        assert!(read_lockfile(&path, process_alive).unwrap().is_some());
+
+        // A reaped child's pid is free until the system wraps around
+        let mut child =
+            Command::new("cmd").args(["/C", "exit"]).spawn().unwrap();
+        let dead_pid = child.id();
+        child.wait().unwrap();
+        write(dead_pid);
+        assert!(read_lockfile(&path, process_alive).is_err());
+    }
+
+    // Both lockfiles are trusted alike, whatever host process has the pid
+    #[cfg(unix)]
+    #[test]
+    fn unix_leaves_the_lockfile_pid_to_the_connection() {
+        let dir = tempfile::tempdir().unwrap();
+        // Above the largest pid Linux hands out
+        let contents = "LeagueClient:4194305:54321:s3cr3t:https";
+        fs::write(dir.path().join(LOCKFILE_NAME), contents).unwrap();
+        let riot_client_path = dir.path().join("riot-client-lockfile");
+        fs::write(&riot_client_path, contents).unwrap();
+
+        let source = LockfileSource {
+            install_dir: dir.path().to_path_buf(),
+        };
+        assert_eq!(source.discover().unwrap().port, "54321");
+        let riot_client = read_riot_client_lockfile(&riot_client_path);
+        assert_eq!(riot_client.unwrap().map(|c| c.port), Some("54321".into()));
+    }
+
+    #[test]
+    fn missing_lockfile_is_none() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(LOCKFILE_NAME);
+        assert_eq!(read_lockfile(&path, |_| true).unwrap(), None);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn scans_every_client_in_procfs() {
+        let dir = tempfile::tempdir().unwrap();
+        let proc_root = dir.path();
+        write_cmdline(
+            proc_root,
+            "100",
+            &[
+                "C:\\Riot Games\\League of Legends\\LeagueClientUx.exe",
+                "--app-port=54321",
+                "--remoting-auth-token=first",
+                "--riotclientport=60000",
+                "--riotclientauthtoken=riot",
+            ],
+        );
+        write_cmdline(
+            proc_root,
+            "200",
+            &[
+                "leagueclientux.exe",
+                "--remoting-auth-token=second",
+                "--app-port=54322",
+            ],
+        );
+        // Other processes and non-pid entries are skipped
+        write_cmdline(proc_root, "300", &["/usr/bin/wine", "--app-port=1"]);
+        write_cmdline(
+            proc_root,
+            "self",
+            &[
+                "LeagueClientUx.exe",
+                "--app-port=1",
+                "--remoting-auth-token=x",
+            ],
+        );
+        fs::create_dir_all(proc_root.join("400")).unwrap();
+        fs::write(proc_root.join("500"), "").unwrap();
+
+        let mut clients = scan_procfs(proc_root).unwrap();
+        clients.sort_by(|a, b| a.port.cmp(&b.port));
+        assert_eq!(
+            clients,
+            vec![
+                PortAndToken {
+                    port: "54321".to_string(),
+                    auth_token: "first".to_string(),
+                    riot_client: Some(Box::new(PortAndToken {
+                        port: "60000".to_string(),
+                        auth_token: "riot".to_string(),
+                        riot_client: None,
+                    })),
+                },
+                PortAndToken {
+                    port: "54322".to_string(),
+                    auth_token: "second".to_string(),
+                    riot_client: None,
+                },
+            ]
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn skips_clients_missing_tokens() {
+        let dir = tempfile::tempdir().unwrap();
+        let proc_root = dir.path();
+        write_cmdline(proc_root, "100", &["LeagueClientUx.exe"]);
+        write_cmdline(
+            proc_root,
+            "200",
+            &["LeagueClientUx.exe", "--app-port=54321"],
+        );
+        write_cmdline(
+            proc_root,
+            "300",
+            &[
+                "LeagueClientUx.exe",
+                "--app-port=54321",
+                "--remoting-auth-token=",
+            ],
+        );
+
+        let err = scan_procfs(proc_root).unwrap_err();
+        assert_eq!(err.to_string(), "LeagueClientUx process not found");
+    }
+
+    #[test]
+    fn ignores_partial_riot_client_args() {
+        let cmdline = [
+            "LeagueClientUx.exe",
+            "--app-port=54321",
+            "--remoting-auth-token=token",
+            "--riotclientport=60000",
+        ]
+        .join("\0");
+        let port_and_token = parse_cmdline(cmdline.as_bytes()).unwrap();
+        assert_eq!(port_and_token.riot_client, None);
+    }
+
+    // Answers with fixed clients, or fails when given none
+    #[derive(Debug)]
+    struct FakeSource {
+        name: &'static str,
+        ports: Vec<&'static str>,
+        every_client: bool,
+        explicit: bool,
+    }
+
+    impl FakeSource {
+        fn new(name: &'static str, ports: &[&'static str]) -> Self {
+            Self {
+                name,
+                ports: ports.to_vec(),
+                every_client: false,
+                explicit: false,
+            }
+        }
+    }
+
+    impl CredentialSource for FakeSource {
+        fn name(&self) -> &'static str {
+            self.name
+        }
+
+        fn discover(&self) -> Result<PortAndToken, Box<dyn StdError>> {
+            Ok(self.discover_all()?.remove(0))
+        }
+
+        fn discover_all(&self) -> Result<Vec<PortAndToken>, Box<dyn StdError>> {
+            if self.ports.is_empty() {
+                return Err("nothing found".into());
+            }
+            Ok(self
+                .ports
+                .iter()
+                .map(|port| PortAndToken {
+                    port: port.to_string(),
+                    auth_token: format!("token-{}", port),
+                    riot_client: None,
+                })
+                .collect())
+        }
+
+        fn sees_every_client(&self) -> bool {
+            self.every_client
+        }
+
+        fn is_explicit(&self) -> bool {
+            self.explicit
+        }
+    }
+
+    fn sources() -> Vec<Box<dyn CredentialSource>> {
+        vec![
+            Box::new(FakeSource {
+                explicit: true,
+                ..FakeSource::new("cli", &[])
+            }),
+            Box::new(FakeSource::new("lockfile", &["1111"])),
+            Box::new(FakeSource {
+                every_client: true,
+                ..FakeSource::new("process", &["1111", "2222"])
+            }),
+        ]
+    }
+
+    fn found_ports(
+        found: &[(&'static str, PortAndToken)],
+    ) -> Vec<(&'static str, String)> {
+        found
+            .iter()
+            .map(|(source, credentials)| (*source, credentials.port.clone()))
+            .collect()
+    }
+
+    #[test]
+    fn first_source_answering_wins() {
+        let found = discover_all(&sources(), false).unwrap();
+        assert_eq!(found_ports(&found), [("lockfile", "1111".to_string())]);
+    }
+
+    #[test]
+    fn multi_client_adds_the_process_scan() {
+        let found = discover_all(&sources(), true).unwrap();
+        assert_eq!(
+            found_ports(&found),
+            [
+                ("lockfile", "1111".to_string()),
+                ("process", "2222".to_string())
+            ]
+        );
+    }
+
+    #[test]
+    fn explicit_credentials_stop_discovery() {
+        let mut sources = sources();
+        sources[0] = Box::new(FakeSource {
+            explicit: true,
+            ..FakeSource::new("cli", &["3333"])
+        });
+        let found = discover_all(&sources, true).unwrap();
+        assert_eq!(found_ports(&found), [("cli", "3333".to_string())]);
+    }
+
+    #[test]
+    fn reports_every_failed_source() {
+        let sources: Vec<Box<dyn CredentialSource>> = vec![
+            Box::new(FakeSource::new("cli", &[])),
+            Box::new(FakeSource::new("lockfile", &[])),
+        ];
+        let e = discover_all(&sources, true).unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "No credentials found (cli: nothing found, lockfile: nothing found)"
+        );
+    }
+}
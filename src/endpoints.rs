@@ -0,0 +1,142 @@
+use reqwest::Method;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use std::collections::HashMap;
+
+use crate::models;
+
+pub trait Endpoint {
+    const METHOD: Method;
+    // Placeholders in braces are filled from params
+    const PATH: &'static str;
+    type Body: Serialize;
+    type Response: DeserializeOwned;
+
+    fn params(&self) -> Vec<(&'static str, String)> {
+        Vec::new()
+    }
+
+    fn body(&self) -> Option<&Self::Body> {
+        None
+    }
+
+    fn path(&self) -> String {
+        self.params().into_iter().fold(
+            Self::PATH.to_string(),
+            |path, (name, value)| {
+                path.replace(&format!("{{{}}}", name), &value)
+            },
+        )
+    }
+}
+
+pub struct PickableSkinIds;
+
+impl Endpoint for PickableSkinIds {
+    const METHOD: Method = Method::GET;
+    const PATH: &'static str = "lol-champ-select/v1/pickable-skin-ids";
+    type Body = ();
+    type Response = Vec<i64>;
+}
+
+pub struct CurrentChampion;
+
+impl Endpoint for CurrentChampion {
+    const METHOD: Method = Method::GET;
+    const PATH: &'static str = "lol-champ-select/v1/current-champion";
+    type Body = ();
+    type Response = i64;
+}
+
+// Cheap enough to poll, unlike /help which lists the whole API
+pub struct GameflowPhase;
+
+impl Endpoint for GameflowPhase {
+    const METHOD: Method = Method::GET;
+    const PATH: &'static str = "lol-gameflow/v1/gameflow-phase";
+    type Body = ();
+    type Response = String;
+}
+
+// Riot Client api, products it has launched keyed by session id
+pub struct ExternalSessions;
+
+impl Endpoint for ExternalSessions {
+    const METHOD: Method = Method::GET;
+    const PATH: &'static str = "product-session/v1/external-sessions";
+    type Body = ();
+    type Response = HashMap<String, models::ProductSessionSession>;
+}
+
+pub struct CurrentSummoner;
+
+impl Endpoint for CurrentSummoner {
+    const METHOD: Method = Method::GET;
+    const PATH: &'static str = "lol-summoner/v1/current-summoner";
+    type Body = ();
+    type Response = models::CurrentSummoner;
+}
+
+pub struct CurrentSummonerIds;
+
+impl Endpoint for CurrentSummonerIds {
+    const METHOD: Method = Method::GET;
+    const PATH: &'static str =
+        "lol-summoner/v1/current-summoner/account-and-summoner-ids";
+    type Body = ();
+    type Response = models::CurrentSummonerAccountAndSummonerIds;
+}
+
+pub struct ChampionSkins {
+    pub summoner_id: i64,
+    pub champion_id: i64,
+}
+
+impl Endpoint for ChampionSkins {
+    const METHOD: Method = Method::GET;
+    const PATH: &'static str =
+        "lol-champions/v1/inventories/{summonerId}/champions/{championId}/skins";
+    type Body = ();
+    type Response = Vec<models::ChampionsCollectionsChampionSkin>;
+
+    fn params(&self) -> Vec<(&'static str, String)> {
+        vec![
+            ("summonerId", self.summoner_id.to_string()),
+            ("championId", self.champion_id.to_string()),
+        ]
+    }
+}
+
+pub struct Session;
+
+impl Endpoint for Session {
+    const METHOD: Method = Method::GET;
+    const PATH: &'static str = "lol-champ-select/v1/session";
+    type Body = ();
+    type Response = models::ChampSelectSession;
+}
+
+pub struct MySelection(pub models::ChampSelectChampSelectMySelection);
+
+impl Endpoint for MySelection {
+    const METHOD: Method = Method::PATCH;
+    const PATH: &'static str = "lol-champ-select/v1/session/my-selection";
+    type Body = models::ChampSelectChampSelectMySelection;
+    type Response = ();
+
+    fn body(&self) -> Option<&Self::Body> {
+        Some(&self.0)
+    }
+}
+
+impl MySelection {
+    pub fn skin(selected_skin_id: i64) -> Self {
+        Self(models::ChampSelectChampSelectMySelection {
+            selected_skin_id,
+            spell1_id: None,
+            spell2_id: None,
+            ward_skin_id: None,
+        })
+    }
+}
@@ -0,0 +1,148 @@
+use std::error::Error as StdError;
+use std::fmt;
+
+use crate::models::LcuErrorBody;
+
+#[derive(Debug)]
+pub enum LcuError {
+    ClientNotRunning,
+    NotLoggedIn,
+    Unauthorized,
+    NotInChampSelect,
+    ChampionNotPicked,
+    HttpStatus {
+        code: u16,
+        body: String,
+    },
+    // Error status with the LCU's standard error payload
+    Api {
+        endpoint: String,
+        code: u16,
+        error: LcuErrorBody,
+    },
+    Decode {
+        endpoint: String,
+        source: serde_json::Error,
+    },
+    Request(reqwest::Error),
+    // The client answered with a certificate the pinned CA didn't issue
+    Tls(reqwest::Error),
+    // What was being picked, e.g. "skins" or "chroma"
+    NoCandidates(&'static str),
+    // Owned candidates exist, but favorites and blacklist rule them all out
+    FilteredOut(&'static str),
+    // Candidates are left, but rarity_weights give every one of them zero odds
+    ZeroWeights(&'static str),
+    InvalidColor(String),
+    // skin_filters.json doesn't parse
+    InvalidFilters(serde_json::Error),
+    // Refused by the guard before reaching the client
+    Blocked(String),
+}
+
+impl LcuError {
+    // Builds the error for a non successful response
+    pub fn from_status(endpoint: &str, code: u16, body: String) -> Self {
+        if code == 401 {
+            return Self::Unauthorized;
+        }
+        match serde_json::from_str::<LcuErrorBody>(&body) {
+            Ok(error) if !error.error_code.is_empty() => Self::Api {
+                endpoint: endpoint.to_string(),
+                code,
+                error,
+            },
+            _ => Self::HttpStatus { code, body },
+        }
+    }
+
+    pub fn status_code(&self) -> Option<u16> {
+        match self {
+            Self::Unauthorized => Some(401),
+            Self::HttpStatus { code, .. } | Self::Api { code, .. } => {
+                Some(*code)
+            }
+            _ => None,
+        }
+    }
+
+    // Champ select endpoints answer 404 outside of a champ select session
+    pub fn or_not_in_champ_select(self) -> Self {
+        match self.status_code() {
+            Some(404) => Self::NotInChampSelect,
+            _ => self,
+        }
+    }
+}
+
+impl fmt::Display for LcuError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::ClientNotRunning => write!(f, "LeagueClient not found!"),
+            Self::NotLoggedIn => write!(f, "Not logged in!"),
+            Self::Unauthorized => write!(f, "LeagueClient refused access!"),
+            Self::NotInChampSelect => write!(f, "Not in champion select!"),
+            Self::ChampionNotPicked => write!(f, "Champion not picked yet!"),
+            Self::HttpStatus { code, body } => {
+                write!(f, "LCU responded {}: {}", code, body)
+            }
+            Self::Api {
+                endpoint,
+                code,
+                error,
+            } => write!(
+                f,
+                "{} responded {} {}: {}",
+                endpoint, code, error.error_code, error.message
+            ),
+            Self::Decode { endpoint, source } => {
+                write!(f, "Failed decoding {}: {}", endpoint, source)
+            }
+            Self::Request(e) => {
+                write!(f, "Request to LeagueClient failed: {}", e)
+            }
+            Self::Tls(e) => {
+                write!(f, "LeagueClient certificate not trusted: {}", e)
+            }
+            Self::NoCandidates(kind) => write!(f, "No {} available!", kind),
+            Self::FilteredOut(kind) => {
+                write!(f, "Favorites and blacklist leave no {}!", kind)
+            }
+            Self::ZeroWeights(kind) => {
+                write!(f, "Rarity weights give no {} a chance!", kind)
+            }
+            Self::InvalidColor(color) => {
+                write!(f, "Invalid chroma color {:?}", color)
+            }
+            Self::InvalidFilters(e) => {
+                write!(f, "Invalid skin_filters.json: {}", e)
+            }
+            Self::Blocked(reason) => write!(f, "Blocked, {}!", reason),
+        }
+    }
+}
+
+impl StdError for LcuError {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        match self {
+            Self::Decode { source, .. } | Self::InvalidFilters(source) => {
+                Some(source)
+            }
+            Self::Request(e) | Self::Tls(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+// The handshake fails inside the connector, the native-tls error is further
+// down the source chain
+impl From<reqwest::Error> for LcuError {
+    fn from(e: reqwest::Error) -> Self {
+        let is_tls = std::iter::successors(e.source(), |e| (*e).source())
+            .any(|source| source.is::<native_tls::Error>());
+        match is_tls {
+            true => Self::Tls(e),
+            false => Self::Request(e),
+        }
+    }
+}
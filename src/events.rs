@@ -0,0 +1,254 @@
+use native_tls::TlsConnector;
+use serde_json::Value;
+use tungstenite::client::IntoClientRequest;
+use tungstenite::stream::MaybeTlsStream;
+use tungstenite::{Connector, Message, WebSocket};
+
+use std::error::Error as StdError;
+use std::io::{Read, Write};
+use std::net::{SocketAddr, TcpStream};
+use std::time::Duration;
+
+use crate::models::{ChampSelectSession, JsonApiEvent};
+
+// WAMP 1.0 message type ids used by the LCU
+const WAMP_SUBSCRIBE: i64 = 5;
+const WAMP_EVENT: i64 = 8;
+
+const CHAMP_SELECT_SESSION_URI: &str = "/lol-champ-select/v1/session";
+const GAMEFLOW_PHASE_URI: &str = "/lol-gameflow/v1/gameflow-phase";
+
+pub type EventSocket = WebSocket<MaybeTlsStream<TcpStream>>;
+
+#[derive(Debug, Clone)]
+pub enum LcuEvent {
+    // None once the session is deleted, i.e. champ select ended
+    ChampSelectSession(Option<Box<ChampSelectSession>>),
+    GameflowPhase(String),
+}
+
+// Topics are the endpoint uri prefixed and with slashes replaced
+fn topic(uri: &str) -> String {
+    format!("OnJsonApiEvent{}", uri.replace('/', "_"))
+}
+
+pub fn connect(
+    port: &str,
+    auth_token_encoded: &str,
+    tls_connector: TlsConnector,
+    connect_timeout: Duration,
+    handshake_timeout: Duration,
+) -> Result<EventSocket, Box<dyn StdError>> {
+    let mut request =
+        format!("wss://127.0.0.1:{}/", port).into_client_request()?;
+    request.headers_mut().insert(
+        "Authorization",
+        format!("Basic {}", auth_token_encoded).parse()?,
+    );
+
+    let addr: SocketAddr = format!("127.0.0.1:{}", port).parse()?;
+    let stream = TcpStream::connect_timeout(&addr, connect_timeout)?;
+    stream.set_read_timeout(Some(handshake_timeout))?;
+    stream.set_write_timeout(Some(handshake_timeout))?;
+    let (mut socket, _) = tungstenite::client_tls_with_config(
+        request,
+        stream,
+        None,
+        Some(Connector::NativeTls(tls_connector)),
+    )
+    .map_err(|e| e.to_string())?;
+
+    subscribe(&mut socket)?;
+
+    // Events can be minutes apart, only the handshake is bounded
+    let stream = match socket.get_ref() {
+        MaybeTlsStream::NativeTls(stream) => stream.get_ref(),
+        MaybeTlsStream::Plain(stream) => stream,
+        _ => return Ok(socket),
+    };
+    stream.set_read_timeout(None)?;
+    Ok(socket)
+}
+
+pub fn subscribe<S: Read + Write>(
+    socket: &mut WebSocket<S>,
+) -> Result<(), Box<dyn StdError>> {
+    for uri in [CHAMP_SELECT_SESSION_URI, GAMEFLOW_PHASE_URI] {
+        let msg = serde_json::to_string(&(WAMP_SUBSCRIBE, topic(uri)))?;
+        socket.send(Message::text(msg))?;
+    }
+    Ok(())
+}
+
+// Blocks until the connection is lost, any socket stand-in can be replayed
+pub fn listen<S: Read + Write>(
+    socket: &mut WebSocket<S>,
+    mut on_event: impl FnMut(LcuEvent),
+) -> Result<(), Box<dyn StdError>> {
+    loop {
+        match socket.read()? {
+            Message::Text(text) => {
+                if let Some(event) = parse_event(&text) {
+                    on_event(event);
+                }
+            }
+            Message::Close(_) => return Err("Connection closed".into()),
+            _ => {}
+        }
+    }
+}
+
+// Message format is [8, "<topic>", {"data": .., "eventType": .., "uri": ..}]
+pub fn parse_event(text: &str) -> Option<LcuEvent> {
+    let (opcode, _topic, payload): (i64, String, Value) =
+        serde_json::from_str(text).ok()?;
+    if opcode != WAMP_EVENT {
+        return None;
+    }
+
+    let event: JsonApiEvent = serde_json::from_value(payload).ok()?;
+    match event.uri.as_str() {
+        CHAMP_SELECT_SESSION_URI if event.event_type == "Delete" => {
+            Some(LcuEvent::ChampSelectSession(None))
+        }
+        CHAMP_SELECT_SESSION_URI => {
+            serde_json::from_value(event.data).ok().map(|session| {
+                LcuEvent::ChampSelectSession(Some(Box::new(session)))
+            })
+        }
+        GAMEFLOW_PHASE_URI => event
+            .data
+            .as_str()
+            .map(|phase| LcuEvent::GameflowPhase(phase.to_string())),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::io::Cursor;
+    use tungstenite::protocol::Role;
+
+    // Frames as the LCU pushed them, with the champ select session cut short
+    const RECORDED_FRAMES: [&str; 6] = [
+        r#"[8,"OnJsonApiEvent_lol-gameflow_v1_gameflow-phase",{"data":"ChampSelect","eventType":"Update","uri":"/lol-gameflow/v1/gameflow-phase"}]"#,
+        r#"[8,"OnJsonApiEvent_lol-champ-select_v1_session",{"data":{"gameId":7001,"queueId":450,"localPlayerCellId":0},"eventType":"Create","uri":"/lol-champ-select/v1/session"}]"#,
+        r#"[8,"OnJsonApiEvent_lol-champ-select_v1_session",{"data":{"gameId":7001,"queueId":450,"timer":{"phase":"FINALIZATION"}},"eventType":"Update","uri":"/lol-champ-select/v1/session"}]"#,
+        r#"[8,"OnJsonApiEvent_lol-chat_v1_me",{"data":{},"eventType":"Update","uri":"/lol-chat/v1/me"}]"#,
+        r#"[8,"OnJsonApiEvent_lol-champ-select_v1_session",{"data":null,"eventType":"Delete","uri":"/lol-champ-select/v1/session"}]"#,
+        r#"[8,"OnJsonApiEvent_lol-gameflow_v1_gameflow-phase",{"data":"InProgress","eventType":"Update","uri":"/lol-gameflow/v1/gameflow-phase"}]"#,
+    ];
+
+    // Reads server frames from a buffer, whatever the client sends is kept
+    struct RecordedStream {
+        frames: Cursor<Vec<u8>>,
+        sent: Vec<u8>,
+    }
+
+    impl Read for RecordedStream {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            self.frames.read(buf)
+        }
+    }
+
+    impl Write for RecordedStream {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.sent.write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn encode_frames(messages: Vec<Message>) -> Vec<u8> {
+        let mut server = WebSocket::from_raw_socket(
+            Cursor::new(Vec::new()),
+            Role::Server,
+            None,
+        );
+        for message in messages {
+            server.send(message).unwrap();
+        }
+        server.get_ref().get_ref().clone()
+    }
+
+    #[test]
+    fn parses_session_create_and_update() {
+        for text in &RECORDED_FRAMES[1..3] {
+            let Some(LcuEvent::ChampSelectSession(Some(session))) =
+                parse_event(text)
+            else {
+                panic!("No session in {}", text);
+            };
+            assert_eq!((session.game_id, session.queue_id), (7001, 450));
+        }
+    }
+
+    #[test]
+    fn parses_session_delete() {
+        assert!(matches!(
+            parse_event(RECORDED_FRAMES[4]),
+            Some(LcuEvent::ChampSelectSession(None))
+        ));
+    }
+
+    #[test]
+    fn parses_gameflow_phase() {
+        assert!(matches!(
+            parse_event(RECORDED_FRAMES[0]),
+            Some(LcuEvent::GameflowPhase(phase)) if phase == "ChampSelect"
+        ));
+    }
+
+    #[test]
+    fn ignores_unknown_uris_and_other_messages() {
+        assert!(parse_event(RECORDED_FRAMES[3]).is_none());
+        // Subscribe and welcome messages and garbage
+        assert!(parse_event(r#"[5,"OnJsonApiEvent"]"#).is_none());
+        assert!(parse_event(r#"[0,"session","1",""]"#).is_none());
+        assert!(parse_event("not json").is_none());
+    }
+
+    #[test]
+    fn listens_until_closed() {
+        let mut messages: Vec<Message> = RECORDED_FRAMES
+            .iter()
+            .map(|text| Message::text(*text))
+            .collect();
+        messages.insert(2, Message::Ping(Vec::new().into()));
+        messages.push(Message::Close(None));
+        let stream = RecordedStream {
+            frames: Cursor::new(encode_frames(messages)),
+            sent: Vec::new(),
+        };
+        let mut socket = WebSocket::from_raw_socket(stream, Role::Client, None);
+
+        let mut events = Vec::new();
+        let err = listen(&mut socket, |event| events.push(event)).unwrap_err();
+
+        assert_eq!(err.to_string(), "Connection closed");
+        let summary: Vec<String> = events
+            .iter()
+            .map(|event| match event {
+                LcuEvent::GameflowPhase(phase) => phase.clone(),
+                LcuEvent::ChampSelectSession(Some(session)) => {
+                    format!("session {}", session.timer.phase)
+                }
+                LcuEvent::ChampSelectSession(None) => "deleted".to_string(),
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                "ChampSelect",
+                "session ",
+                "session FINALIZATION",
+                "deleted",
+                "InProgress",
+            ]
+        );
+    }
+}
@@ -0,0 +1,148 @@
+use serde::{Deserialize, Serialize};
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use crate::error::LcuError;
+
+// Lists hold skin and chroma ids alike, each is only checked against ids of
+// its own kind
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ChampionFilter {
+    // When any apply, only these can be picked
+    pub favorites: Vec<i64>,
+    pub blacklist: Vec<i64>,
+}
+
+impl ChampionFilter {
+    // known lists every id of the candidates' kind, owned or not
+    pub fn apply<'a, T>(
+        &self,
+        candidates: Vec<&'a T>,
+        id: impl Fn(&T) -> i64,
+        known: &[i64],
+    ) -> Vec<&'a T> {
+        let favorites: Vec<i64> = self
+            .favorites
+            .iter()
+            .copied()
+            .filter(|favorite| known.contains(favorite))
+            .collect();
+        candidates
+            .into_iter()
+            .filter(|c| favorites.is_empty() || favorites.contains(&id(c)))
+            .filter(|c| !self.blacklist.contains(&id(c)))
+            .collect()
+    }
+}
+
+// Filters by champion id
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct SkinFilters(pub HashMap<i64, ChampionFilter>);
+
+impl SkinFilters {
+    // The file is edited by hand, a typo must not quietly lift the blacklist
+    pub fn load(path: &Path) -> Result<Self, LcuError> {
+        let Ok(filters_str) = fs::read_to_string(path) else {
+            return Ok(Self::default());
+        };
+        serde_json::from_str(&filters_str).map_err(LcuError::InvalidFilters)
+    }
+
+    pub fn champion(&self, champion_id: i64) -> ChampionFilter {
+        self.0.get(&champion_id).cloned().unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KNOWN: [i64; 4] = [1, 2, 3, 4];
+
+    fn apply(
+        filter: &ChampionFilter,
+        candidates: &[i64],
+        known: &[i64],
+    ) -> Vec<i64> {
+        filter
+            .apply(candidates.iter().collect(), |id| *id, known)
+            .into_iter()
+            .copied()
+            .collect()
+    }
+
+    #[test]
+    fn favorites_only_allow_themselves() {
+        let filter = ChampionFilter {
+            favorites: vec![2, 4],
+            ..Default::default()
+        };
+        assert_eq!(apply(&filter, &[1, 2, 3], &KNOWN), [2]);
+    }
+
+    #[test]
+    fn blacklist_removes_its_ids() {
+        let filter = ChampionFilter {
+            blacklist: vec![2],
+            ..Default::default()
+        };
+        assert_eq!(apply(&filter, &[1, 2, 3], &KNOWN), [1, 3]);
+        assert_eq!(apply(&filter, &[2], &KNOWN), Vec::<i64>::new());
+    }
+
+    #[test]
+    fn unknown_favorites_are_ignored() {
+        // e.g. a skin that left the collection
+        let filter = ChampionFilter {
+            favorites: vec![9],
+            ..Default::default()
+        };
+        assert_eq!(apply(&filter, &[1, 2, 3], &KNOWN), [1, 2, 3]);
+
+        let filter = ChampionFilter {
+            favorites: vec![2, 9],
+            ..Default::default()
+        };
+        assert_eq!(apply(&filter, &[1, 2, 3], &KNOWN), [2]);
+    }
+
+    #[test]
+    fn chroma_ids_only_filter_chromas() {
+        let filter = ChampionFilter {
+            favorites: vec![2, 20],
+            blacklist: vec![21],
+        };
+        let chroma_ids = [20, 21, 22];
+        assert_eq!(apply(&filter, &[1, 2, 3], &KNOWN), [2]);
+        assert_eq!(apply(&filter, &[20, 21, 22], &chroma_ids), [20]);
+
+        // Only skin favorites, every chroma stays but the blacklisted one
+        let filter = ChampionFilter {
+            favorites: vec![2],
+            blacklist: vec![21],
+        };
+        assert_eq!(apply(&filter, &[20, 21, 22], &chroma_ids), [20, 22]);
+    }
+
+    #[test]
+    fn load_reports_malformed_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("skin_filters.json");
+        assert_eq!(SkinFilters::load(&path).unwrap(), SkinFilters::default());
+
+        fs::write(&path, r#"{"103": {"blacklist": [103001]}}"#).unwrap();
+        let filters = SkinFilters::load(&path).unwrap();
+        assert_eq!(filters.champion(103).blacklist, [103001]);
+        assert_eq!(filters.champion(22), ChampionFilter::default());
+
+        fs::write(&path, r#"{"103": {"blacklist": [103001,]}}"#).unwrap();
+        assert!(matches!(
+            SkinFilters::load(&path),
+            Err(LcuError::InvalidFilters(_))
+        ));
+    }
+}
@@ -0,0 +1,171 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use std::error::Error as StdError;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use crate::config::Config;
+use crate::filters::SkinFilters;
+use crate::history::PlayHistory;
+use crate::shufflebag::ShuffleBags;
+
+const REDACTED: &str = "<redacted>";
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct Exchange {
+    pub method: String,
+    pub path: String,
+    pub request_body: Option<Value>,
+    pub status: u16,
+    pub response_body: String,
+}
+
+// Persisted state the picks depend on
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct PickState {
+    pub skin_filters: SkinFilters,
+    pub shuffle_bags: ShuffleBags,
+    pub play_history: PlayHistory,
+}
+
+impl PickState {
+    pub fn load(config: &Config) -> Self {
+        Self {
+            // Picks fail on malformed filters either way
+            skin_filters: SkinFilters::load(&config.skin_filters_path())
+                .unwrap_or_default(),
+            shuffle_bags: ShuffleBags::load(&config.shuffle_bags_path()),
+            play_history: PlayHistory::load(&config.play_history_path()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct Fixture {
+    pub exchanges: Vec<Exchange>,
+    // Seed of every pick in order, the settings and the state as recording
+    // started, so a replay picks the same on any machine
+    pub seeds: Vec<u64>,
+    pub config: Option<Config>,
+    pub state: PickState,
+}
+
+impl Fixture {
+    pub fn load(path: &Path) -> Result<Self, Box<dyn StdError>> {
+        Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), Box<dyn StdError>> {
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+}
+
+// Writes the fixture out after every exchange so a crash keeps what was seen,
+// one recorder is shared by every client so none overwrites the others
+#[derive(Debug)]
+pub struct Recorder {
+    path: PathBuf,
+    fixture: Mutex<Fixture>,
+}
+
+impl Recorder {
+    pub fn new(path: &Path, config: &Config) -> Self {
+        Self {
+            path: path.to_path_buf(),
+            fixture: Mutex::new(Fixture {
+                config: Some(config.clone()),
+                state: PickState::load(config),
+                ..Default::default()
+            }),
+        }
+    }
+
+    pub fn record(&self, mut exchange: Exchange, secrets: &[&str]) {
+        exchange.response_body = redact(&exchange.response_body, secrets);
+        exchange.request_body = exchange.request_body.map(|body| {
+            let body = redact(&body.to_string(), secrets);
+            serde_json::from_str(&body).unwrap_or(Value::Null)
+        });
+
+        self.update(|fixture| fixture.exchanges.push(exchange));
+    }
+
+    pub fn record_seed(&self, seed: u64) {
+        self.update(|fixture| fixture.seeds.push(seed));
+    }
+
+    fn update(&self, f: impl FnOnce(&mut Fixture)) {
+        let mut fixture =
+            self.fixture.lock().unwrap_or_else(|e| e.into_inner());
+        f(&mut fixture);
+        if let Err(e) = fixture.save(&self.path) {
+            println!("Failed saving fixture {}: {}", self.path.display(), e);
+        }
+    }
+}
+
+fn redact(text: &str, secrets: &[&str]) -> String {
+    secrets
+        .iter()
+        .filter(|secret| !secret.is_empty())
+        .fold(text.to_string(), |text, secret| {
+            text.replace(secret, REDACTED)
+        })
+}
+
+// Serves recorded exchanges in order, repeating the last one per route.
+// The replayed picks update the recorded state in place of the files
+#[derive(Debug)]
+pub struct Replay {
+    exchanges: Mutex<Vec<(Exchange, bool)>>,
+    seeds: Mutex<Vec<u64>>,
+    state: Mutex<PickState>,
+}
+
+impl Replay {
+    pub fn new(fixture: Fixture) -> Self {
+        let mut seeds = fixture.seeds;
+        seeds.reverse();
+        Self {
+            exchanges: Mutex::new(
+                fixture.exchanges.into_iter().map(|e| (e, false)).collect(),
+            ),
+            seeds: Mutex::new(seeds),
+            state: Mutex::new(fixture.state),
+        }
+    }
+
+    pub fn with_state<T>(&self, f: impl FnOnce(&mut PickState) -> T) -> T {
+        f(&mut self.state.lock().unwrap_or_else(|e| e.into_inner()))
+    }
+
+    // None once every recorded seed was used
+    pub fn next_seed(&self) -> Option<u64> {
+        self.seeds.lock().unwrap_or_else(|e| e.into_inner()).pop()
+    }
+
+    pub fn respond(&self, method: &str, path: &str) -> (u16, String) {
+        let mut exchanges =
+            self.exchanges.lock().unwrap_or_else(|e| e.into_inner());
+        let matches = |e: &Exchange| e.method == method && e.path == path;
+
+        if let Some((exchange, used)) =
+            exchanges.iter_mut().find(|(e, used)| !used && matches(e))
+        {
+            *used = true;
+            return (exchange.status, exchange.response_body.clone());
+        }
+        exchanges
+            .iter()
+            .rev()
+            .find(|(e, _)| matches(e))
+            .map(|(e, _)| (e.status, e.response_body.clone()))
+            .unwrap_or((404, String::new()))
+    }
+}
@@ -0,0 +1,139 @@
+use crate::config::Config;
+use crate::models::ChampSelectSession;
+
+// Decides whether a mutating request may be sent in the current champ select
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MutationGuard {
+    // None allows every queue
+    queues: Option<Vec<i64>>,
+    custom_games: bool,
+    phases: Vec<String>,
+}
+
+impl MutationGuard {
+    pub fn new(config: &Config) -> Self {
+        Self {
+            queues: config.guard_queues.clone(),
+            custom_games: config.guard_custom_games,
+            phases: config.guard_phases.clone(),
+        }
+    }
+
+    // The reason the request is blocked, if it is
+    pub fn check(&self, session: &ChampSelectSession) -> Result<(), String> {
+        if session.is_custom_game {
+            if !self.custom_games {
+                return Err("custom games are not allowed".to_string());
+            }
+        } else if let Some(queues) = &self.queues {
+            // Custom games have no queue of their own
+            if !queues.contains(&session.queue_id) {
+                return Err(format!(
+                    "queue {} is not allowed",
+                    session.queue_id
+                ));
+            }
+        }
+        if !self.phases.contains(&session.timer.phase) {
+            return Err(format!(
+                "not allowed during {}",
+                match session.timer.phase.as_str() {
+                    "" => "an unknown phase",
+                    phase => phase,
+                }
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use serde_json::{json, Value};
+
+    const CHAMP_SELECT: &str =
+        include_str!("../mock-lcu/fixtures/champ_select.json");
+
+    // The mock's champ select session, ARAM in finalization, with the
+    // given fields replaced
+    fn session(fields: Value) -> ChampSelectSession {
+        let mut state: Value = serde_json::from_str(CHAMP_SELECT).unwrap();
+        let session = state["session"].as_object_mut().unwrap();
+        for (key, value) in fields.as_object().unwrap() {
+            session.insert(key.clone(), value.clone());
+        }
+        serde_json::from_value(state["session"].clone()).unwrap()
+    }
+
+    fn guard() -> MutationGuard {
+        MutationGuard::new(&Config::default())
+    }
+
+    #[test]
+    fn allows_aram_in_finalization() {
+        assert_eq!(guard().check(&session(json!({}))), Ok(()));
+    }
+
+    #[test]
+    fn blocks_ranked_and_clash_by_default() {
+        for queue_id in [420, 440, 700] {
+            assert_eq!(
+                guard().check(&session(json!({ "queueId": queue_id }))),
+                Err(format!("queue {} is not allowed", queue_id))
+            );
+        }
+    }
+
+    #[test]
+    fn no_queue_list_allows_every_queue() {
+        let guard = MutationGuard::new(&Config {
+            guard_queues: None,
+            ..Default::default()
+        });
+        assert_eq!(guard.check(&session(json!({ "queueId": 420 }))), Ok(()));
+    }
+
+    #[test]
+    fn custom_games_ignore_the_queue_list() {
+        let custom = session(json!({ "queueId": 0, "isCustomGame": true }));
+        assert_eq!(guard().check(&custom), Ok(()));
+
+        let guard = MutationGuard::new(&Config {
+            guard_custom_games: false,
+            ..Default::default()
+        });
+        assert_eq!(
+            guard.check(&custom),
+            Err("custom games are not allowed".to_string())
+        );
+    }
+
+    #[test]
+    fn blocks_outside_the_allowed_phases() {
+        let planning = session(json!({ "timer": { "phase": "PLANNING" } }));
+        assert_eq!(
+            guard().check(&planning),
+            Err("not allowed during PLANNING".to_string())
+        );
+
+        let unknown = session(json!({ "timer": {} }));
+        assert_eq!(
+            guard().check(&unknown),
+            Err("not allowed during an unknown phase".to_string())
+        );
+    }
+
+    #[test]
+    fn queue_is_checked_before_the_phase() {
+        let ranked_planning = session(json!({
+            "queueId": 420,
+            "timer": { "phase": "PLANNING" }
+        }));
+        assert_eq!(
+            guard().check(&ranked_planning),
+            Err("queue 420 is not allowed".to_string())
+        );
+    }
+}
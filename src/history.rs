@@ -0,0 +1,250 @@
+use serde::{Deserialize, Serialize};
+
+use std::collections::HashMap;
+use std::error::Error as StdError;
+use std::fs;
+use std::path::Path;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::auto;
+use crate::events::LcuEvent;
+use crate::models::ChampionsCollectionsChampionSkin;
+
+// Even the skin just played keeps a small chance, so a champion with a
+// single skin can still be rolled
+const MIN_RECENCY_FACTOR: f64 = 0.02;
+
+// When each skin was last applied, in seconds since the epoch, by summoner
+// id then skin id
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct PlayHistory(pub HashMap<i64, HashMap<i64, u64>>);
+
+impl PlayHistory {
+    pub fn load(path: &Path) -> Self {
+        let Ok(history_str) = fs::read_to_string(path) else {
+            return Self::default();
+        };
+        serde_json::from_str(&history_str).unwrap_or_else(|e| {
+            dbg!(e);
+            Self::default()
+        })
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), Box<dyn StdError>> {
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    pub fn record(&mut self, summoner_id: i64, skin_id: i64, now: SystemTime) {
+        let secs = now.duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+        self.0.entry(summoner_id).or_default().insert(skin_id, secs);
+    }
+
+    pub fn last_played(
+        &self,
+        summoner_id: i64,
+        skin_id: i64,
+    ) -> Option<SystemTime> {
+        let secs = self.0.get(&summoner_id)?.get(&skin_id)?;
+        Some(UNIX_EPOCH + Duration::from_secs(*secs))
+    }
+
+    // Odds factor of the skin in least_recent. The client only knows the
+    // last selected skin, it counts as just played
+    pub fn recency(
+        &self,
+        summoner_id: i64,
+        skin: &ChampionsCollectionsChampionSkin,
+        now: SystemTime,
+        half_life: Duration,
+    ) -> f64 {
+        let last_played = match skin.last_selected {
+            true => Some(now),
+            false => self.last_played(summoner_id, skin.id),
+        };
+        recency_factor(last_played, now, half_life)
+    }
+}
+
+// The local player's skin as selected in champ select, possibly a chroma
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Played {
+    pub summoner_id: i64,
+    pub champion_id: i64,
+    pub skin_id: i64,
+}
+
+// Follows the primary client's events, a skin only counts as played with
+// whatever was selected last once the game is in progress, rerolls and
+// dodged champ selects don't
+#[derive(Debug, Clone, Default)]
+pub struct PlayTracker {
+    selected: Option<Played>,
+}
+
+impl PlayTracker {
+    pub fn on_event(&mut self, event: &LcuEvent) -> Option<Played> {
+        match event {
+            LcuEvent::ChampSelectSession(Some(session)) => {
+                self.selected = auto::local_player(session)
+                    .filter(|me| {
+                        me.champion_id != 0 && me.selected_skin_id != 0
+                    })
+                    .map(|me| Played {
+                        summoner_id: me.summoner_id,
+                        champion_id: me.champion_id,
+                        skin_id: me.selected_skin_id,
+                    });
+                None
+            }
+            // The session ends right before the game starts
+            LcuEvent::ChampSelectSession(None) => None,
+            LcuEvent::GameflowPhase(phase) => match phase.as_str() {
+                "InProgress" => self.selected.take(),
+                "ChampSelect" | "GameStart" => None,
+                _ => {
+                    self.selected = None;
+                    None
+                }
+            },
+        }
+    }
+}
+
+// Recovers towards 1 as the skin goes unused, half the remaining way with
+// every half_life, e.g. a day gives 0.09 and a month 0.95 with a week
+pub fn recency_factor(
+    last_played: Option<SystemTime>,
+    now: SystemTime,
+    half_life: Duration,
+) -> f64 {
+    let Some(last_played) = last_played else {
+        return 1.0;
+    };
+    if half_life.is_zero() {
+        return 1.0;
+    }
+    let age = now.duration_since(last_played).unwrap_or_default();
+    let factor = 1.0 - 0.5f64.powf(age.as_secs_f64() / half_life.as_secs_f64());
+    factor.max(MIN_RECENCY_FACTOR)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::config::Config;
+    use crate::selection;
+
+    use std::collections::HashMap;
+
+    const SUMMONER_ID: i64 = 1001;
+    const DAY: Duration = Duration::from_secs(24 * 60 * 60);
+    const WEEK: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+    fn now() -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(1_700_000_000)
+    }
+
+    fn skin(id: i64) -> ChampionsCollectionsChampionSkin {
+        ChampionsCollectionsChampionSkin {
+            id,
+            ..Default::default()
+        }
+    }
+
+    fn assert_close(factor: f64, expected: f64) {
+        assert!(
+            (factor - expected).abs() < 0.005,
+            "factor {:.4}, expected {:.4}",
+            factor,
+            expected
+        );
+    }
+
+    #[test]
+    fn recency_recovers_with_every_half_life() {
+        let factor = |age| recency_factor(Some(now() - age), now(), WEEK);
+        assert_eq!(recency_factor(None, now(), WEEK), 1.0);
+        assert_eq!(factor(Duration::ZERO), MIN_RECENCY_FACTOR);
+        assert_close(factor(DAY), 0.094);
+        assert_close(factor(WEEK), 0.5);
+        assert_close(factor(2 * WEEK), 0.75);
+        assert_close(factor(30 * DAY), 0.949);
+        assert!(factor(DAY) * 5.0 < factor(30 * DAY));
+        // A clock set back counts as just played
+        assert_eq!(
+            recency_factor(Some(now() + DAY), now(), WEEK),
+            MIN_RECENCY_FACTOR
+        );
+    }
+
+    #[test]
+    fn half_life_comes_from_lru_half_life_days() {
+        let half_life = |days| {
+            Config {
+                lru_half_life_days: days,
+                ..Default::default()
+            }
+            .lru_half_life()
+        };
+        let yesterday = Some(now() - DAY);
+        assert_close(recency_factor(yesterday, now(), half_life(1.0)), 0.5);
+        assert_close(recency_factor(yesterday, now(), half_life(0.5)), 0.75);
+        assert_close(recency_factor(yesterday, now(), half_life(7.0)), 0.094);
+        // Turned off, every skin weighs the same
+        assert_eq!(recency_factor(yesterday, now(), half_life(0.0)), 1.0);
+        assert_eq!(recency_factor(yesterday, now(), half_life(-1.0)), 1.0);
+    }
+
+    #[test]
+    fn recency_reads_history_and_last_selected() {
+        let mut history = PlayHistory::default();
+        history.record(SUMMONER_ID, 1, now() - DAY);
+        history.record(SUMMONER_ID, 2, now() - 30 * DAY);
+        // Another account's games don't count
+        history.record(2002, 4, now());
+        let last_selected = ChampionsCollectionsChampionSkin {
+            last_selected: true,
+            ..skin(3)
+        };
+        let recency = |skin: &ChampionsCollectionsChampionSkin| {
+            history.recency(SUMMONER_ID, skin, now(), WEEK)
+        };
+
+        assert_close(recency(&skin(1)), 0.094);
+        assert_close(recency(&skin(2)), 0.949);
+        assert_eq!(recency(&last_selected), MIN_RECENCY_FACTOR);
+        assert_eq!(recency(&skin(4)), 1.0);
+    }
+
+    #[test]
+    fn skins_played_lately_are_picked_less() {
+        let mut history = PlayHistory::default();
+        history.record(SUMMONER_ID, 1, now() - DAY);
+        history.record(SUMMONER_ID, 2, now() - 30 * DAY);
+        let skins = [skin(1), skin(2)];
+        let candidates: Vec<&ChampionsCollectionsChampionSkin> =
+            skins.iter().collect();
+        let weight = |skin: &ChampionsCollectionsChampionSkin| {
+            history.recency(SUMMONER_ID, skin, now(), WEEK)
+        };
+
+        const DRAWS: usize = 40_000;
+        let mut rng = selection::seeded_rng(7);
+        let mut counts = HashMap::new();
+        for _ in 0..DRAWS {
+            let picked =
+                selection::pick_skin(&candidates, &weight, &mut rng).unwrap();
+            *counts.entry(picked.id).or_insert(0) += 1;
+        }
+        // 0.094 against 0.949
+        let share = counts[&1] as f64 / DRAWS as f64;
+        assert!(
+            (share - 0.09).abs() < 0.01,
+            "drawn {:.3} of the time",
+            share
+        );
+    }
+}
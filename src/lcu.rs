@@ -1,118 +1,199 @@
 use base64::{engine::general_purpose, Engine as _};
+use native_tls::TlsConnector;
 use rand::prelude::IndexedRandom;
-use regex::Regex;
-use reqwest::{blocking::Client, header};
+use rand::{Rng, RngExt};
+use reqwest::{header, Certificate, Client, Method};
+use serde::de::DeserializeOwned;
+use tokio::runtime::{self, Runtime};
 
 use std::error::Error as StdError;
-use std::os::windows::process::CommandExt;
-use std::process::Command;
-
-use crate::models::{self, Chroma};
-
-type ChromaTextAndColor = (String, u32);
-
-#[derive(Debug, Clone, Default)]
-struct PortAndToken {
-    port: String,
-    auth_token: String,
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+use crate::audit::{self, AuditEntry};
+use crate::config::Config;
+use crate::discovery::PortAndToken;
+use crate::endpoints::{self, Endpoint};
+use crate::error::LcuError;
+use crate::events::{self, EventSocket};
+use crate::filters::ChampionFilter;
+use crate::filters::SkinFilters;
+use crate::fixtures::{Exchange, Fixture, Recorder, Replay};
+use crate::guard::MutationGuard;
+use crate::history::{PlayHistory, Played};
+use crate::models::{ChampionsCollectionsChampionSkin, Chroma};
+use crate::scheduler;
+use crate::selection::{
+    self, ChromaMode, ChromaOutcome, RarityWeights, RollOutcome, SkinMode,
+};
+use crate::shufflebag::ShuffleBags;
+
+// Like a RollOutcome, replaying the seed picks the same chroma again
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChromaTextAndColor {
+    pub seed: u64,
+    pub text: String,
+    pub color: u32,
 }
 
-#[derive(Debug, Clone, Default)]
-pub struct GameClient {
-    port: String,
-    auth_token: String,
-    auth_token_encoded: String,
-    client: Client,
+// What the client is doing, read from the gameflow phase
+#[derive(Debug, Clone, PartialEq)]
+pub enum ClientHealth {
+    NotLoggedIn,
+    Idle,
+    Lobby,
+    ChampSelect,
+    InGame,
+    Other(String),
 }
 
-fn build_wmic_wmi() -> Result<PortAndToken, Box<dyn StdError>> {
-    let re_port = Regex::new(r"--app-port=([0-9]+)")?;
-    let re_auth_token = Regex::new(r"--remoting-auth-token=([\w-]+)")?;
-
-    // Try WMIC first (Windows 10)
-    let wmic_cmd = Command::new("wmic")
-        .args([
-            "PROCESS",
-            "WHERE",
-            "name='LeagueClientUx.exe'",
-            "GET",
-            "commandline",
-        ])
-        .creation_flags(0x08000000)
-        .output();
-
-    let output_string: String;
-    let cmd_output_str: &str = match wmic_cmd {
-        Ok(ref out) if out.status.success() => {
-            output_string = String::from_utf8_lossy(&out.stdout).to_string();
-            &output_string
-        }
-        _ => {
-            // When wmic fails, try PowerShell WMI (Windows 11)
-            let wmi_cmd = r#"Get-CimInstance Win32_Process | Where-Object { $_.Name -eq 'LeagueClientUx.exe' } | Select-Object -ExpandProperty CommandLine"#;
-            let wmi_out = Command::new("powershell")
-                .args(["-Command", wmi_cmd])
-                .creation_flags(0x08000000)
-                .output()?;
-            output_string =
-                String::from_utf8_lossy(&wmi_out.stdout).to_string();
-            &output_string
+impl ClientHealth {
+    // Gameflow phase events are mapped alike, so the status reads the same
+    pub fn from_phase(phase: String) -> Self {
+        match phase.as_str() {
+            "None" => Self::Idle,
+            "Lobby" | "Matchmaking" | "ReadyCheck" => Self::Lobby,
+            "ChampSelect" => Self::ChampSelect,
+            "GameStart" | "InProgress" | "Reconnect" => Self::InGame,
+            _ => Self::Other(phase),
         }
-    };
-
-    let port = re_port
-        .captures(cmd_output_str)
-        .and_then(|v| v.get(1))
-        .map(|m| m.as_str().to_string())
-        .ok_or("Port not found")?;
-
-    let auth_token = re_auth_token
-        .captures(cmd_output_str)
-        .and_then(|v| v.get(1))
-        .map(|m| m.as_str().to_string())
-        .ok_or("Auth token not found")?;
-
-    Ok(PortAndToken { port, auth_token })
+    }
 }
 
-impl GameClient {
-    pub fn new() -> Self {
-        let mut client = Self::default();
-        let res = client.build_client();
-        if res.is_err() {
-            println!("LCU client not available");
+impl fmt::Display for ClientHealth {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotLoggedIn => write!(f, "not logged in"),
+            Self::Idle => write!(f, "idle"),
+            Self::Lobby => write!(f, "in lobby"),
+            Self::ChampSelect => write!(f, "in champ select"),
+            Self::InGame => write!(f, "in game"),
+            Self::Other(phase) => write!(f, "{}", phase),
         }
+    }
+}
 
-        // Development Debugging
-        #[cfg(debug_assertions)]
-        println!(
-            "Port {}\nAuth {}\nEncoded {}",
-            client.port, client.auth_token, client.auth_token_encoded
-        );
+struct SkinCandidates {
+    summoner_id: i64,
+    champion_id: i64,
+    skins: Vec<ChampionsCollectionsChampionSkin>,
+    filter: ChampionFilter,
+    // Every chroma of the champion, for telling chroma favorites apart
+    chroma_ids: Vec<i64>,
+}
 
-        client
+impl SkinCandidates {
+    // Owned chromas of the skin that pass the filters
+    fn chromas<'a>(
+        &self,
+        skin: &'a ChampionsCollectionsChampionSkin,
+    ) -> Vec<&'a Chroma> {
+        let owned = skin.chromas.iter().filter(|c| c.ownership.owned);
+        self.filter
+            .apply(owned.collect(), |chroma| chroma.id, &self.chroma_ids)
     }
+}
+
+// Public root CA the LCU certificates are issued from
+pub const RIOT_ROOT_CA: &[u8] = include_bytes!("assets/riotgames.pem");
 
-    pub fn status(&self) -> bool {
-        let url = self.build_url("help");
-        self.client.get(url).send().is_ok()
+// The bundled Riot root unless another CA is configured
+pub fn read_root_ca(root_ca: Option<&Path>) -> io::Result<Vec<u8>> {
+    match root_ca {
+        Some(path) => fs::read(path),
+        None => Ok(RIOT_ROOT_CA.to_vec()),
     }
+}
+
+// How the LCU and the Riot Client api are reached, both are verified alike
+#[derive(Debug, Clone, Default)]
+pub struct ClientSettings {
+    insecure_tls: bool,
+    root_ca: Option<PathBuf>,
+    connect_timeout: Duration,
+    request_timeout: Duration,
+}
 
-    pub fn retry(&mut self) -> Result<(), Box<dyn StdError>> {
-        match self.status() {
-            true => Ok(()),
-            false => self.build_client(),
+impl ClientSettings {
+    pub fn new(config: &Config) -> Self {
+        Self {
+            insecure_tls: config.insecure_tls,
+            root_ca: config.root_ca.clone(),
+            connect_timeout: config.connect_timeout(),
+            request_timeout: config.request_timeout(),
         }
     }
 
-    fn build_url(&self, path: &str) -> String {
-        let path = path.trim_start_matches("/");
-        format!("https://127.0.0.1:{}/{}", self.port, path)
+    fn tls_connector(&self) -> Result<TlsConnector, Box<dyn StdError>> {
+        let mut builder = TlsConnector::builder();
+        if self.insecure_tls {
+            builder.danger_accept_invalid_certs(true);
+        } else {
+            builder.disable_built_in_roots(true).add_root_certificate(
+                native_tls::Certificate::from_pem(&read_root_ca(
+                    self.root_ca.as_deref(),
+                )?)?,
+            );
+        }
+        Ok(builder.build()?)
     }
+}
 
-    fn build_client(&mut self) -> Result<(), Box<dyn StdError>> {
-        let port_and_token = build_wmic_wmi()?;
+// Authenticated http client for one port. The Riot Client api speaks the
+// same auth as the LCU, the guard and replays are the LCU client's own
+#[derive(Debug, Clone, Default)]
+pub struct ApiClient {
+    port: String,
+    auth_token: String,
+    auth_token_encoded: String,
+    client: Client,
+    recorder: Option<Arc<Recorder>>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct AsyncGameClient {
+    source: Option<&'static str>,
+    settings: ClientSettings,
+    api: ApiClient,
+    skin_mode: SkinMode,
+    group_phrase: String,
+    lru_half_life: Duration,
+    rarity_weights: RarityWeights,
+    chroma_mode: ChromaMode,
+    chroma_chance: f64,
+    // Nothing is read or saved without a path
+    shuffle_bags_path: Option<PathBuf>,
+    skin_filters_path: Option<PathBuf>,
+    play_history_path: Option<PathBuf>,
+    guard: MutationGuard,
+    // Replays aren't audited
+    audit_log_path: Option<PathBuf>,
+    // Serves recorded fixtures instead of talking to a client
+    replay: Option<Arc<Replay>>,
+}
+
+// Blocking facade over AsyncGameClient for the GUI threads
+#[derive(Debug, Clone)]
+pub struct GameClient {
+    inner: AsyncGameClient,
+    runtime: Arc<Runtime>,
+}
 
+impl ApiClient {
+    pub fn set_recorder(&mut self, recorder: Arc<Recorder>) {
+        self.recorder = Some(recorder);
+    }
+
+    // Rebuilds the http client for freshly discovered credentials
+    pub fn connect(
+        &mut self,
+        settings: &ClientSettings,
+        port_and_token: PortAndToken,
+    ) -> Result<(), Box<dyn StdError>> {
         self.port = port_and_token.port;
         self.auth_token = port_and_token.auth_token;
         self.auth_token_encoded = general_purpose::STANDARD
@@ -126,164 +207,547 @@ impl GameClient {
             )?,
         );
 
-        let lcu_client = Client::builder()
+        let builder = Client::builder()
             .default_headers(headers)
-            .danger_accept_invalid_certs(true)
-            .build()?;
+            .connect_timeout(settings.connect_timeout)
+            .timeout(settings.request_timeout);
+        let builder = if settings.insecure_tls {
+            println!("Certificate validation disabled, LCU is not verified");
+            builder.danger_accept_invalid_certs(true)
+        } else {
+            builder.tls_backend_native().tls_certs_only([
+                Certificate::from_pem(&read_root_ca(
+                    settings.root_ca.as_deref(),
+                )?)?,
+            ])
+        };
 
-        self.client = lcu_client;
+        self.client = builder.build()?;
         Ok(())
     }
 
-    pub fn call_champ_select_v1_pickable_skin_ids(
-        &self,
-    ) -> Result<Vec<i64>, Box<dyn StdError>> {
-        let url = self.build_url("lol-champ-select/v1/pickable-skin-ids");
-        let res = self.client.get(url).send()?;
-        let res_str = res.text()?;
-        let result: Vec<i64> = serde_json::from_str(&res_str)?;
-        Ok(result)
+    fn build_url(&self, path: &str) -> String {
+        let path = path.trim_start_matches("/");
+        format!("https://127.0.0.1:{}/{}", self.port, path)
     }
 
-    pub fn call_champ_select_v1_current_champion(
-        &self,
-    ) -> Result<i64, Box<dyn StdError>> {
-        let url = self.build_url("lol-champ-select/v1/current-champion");
-        let res = self.client.get(url).send()?;
-        let res_str = res.text()?;
-        let result: i64 = serde_json::from_str(&res_str)?;
-        Ok(result)
+    // Body of a successful response, endpoint only names the path in errors
+    fn check_status(
+        endpoint: &str,
+        status: u16,
+        res_str: String,
+    ) -> Result<String, LcuError> {
+        if !(200..300).contains(&status) {
+            return Err(LcuError::from_status(endpoint, status, res_str));
+        }
+        Ok(res_str)
+    }
+
+    fn read_response<T: DeserializeOwned>(
+        endpoint: &str,
+        status: u16,
+        res_str: String,
+    ) -> Result<T, LcuError> {
+        let res_str = Self::check_status(endpoint, status, res_str)?;
+        // Endpoints without content, e.g. 204 responses, decode from null
+        let res_str = match res_str.trim() {
+            "" => "null",
+            res_str => res_str,
+        };
+        serde_json::from_str(res_str).map_err(|source| LcuError::Decode {
+            endpoint: endpoint.to_string(),
+            source,
+        })
     }
 
-    pub fn call_summoner_v1_current_summoner_account_and_summoner_ids(
+    pub async fn send<E: Endpoint>(
         &self,
-    ) -> Result<models::CurrentSummonerAccountAndSummonerIds, Box<dyn StdError>>
-    {
-        let url = self.build_url(
-            "lol-summoner/v1/current-summoner/account-and-summoner-ids",
+        endpoint: &E,
+    ) -> Result<E::Response, LcuError> {
+        let path = endpoint.path();
+
+        #[cfg(debug_assertions)]
+        println!("{} /{}", E::METHOD, path);
+
+        let mut req = self.client.request(E::METHOD, self.build_url(&path));
+        if let Some(body) = endpoint.body() {
+            req = req.json(body);
+        }
+        let res = req.send().await?;
+        let status = res.status().as_u16();
+        let res_str = res.text().await?;
+
+        if let Some(recorder) = &self.recorder {
+            recorder.record(
+                Exchange {
+                    method: E::METHOD.to_string(),
+                    path: path.clone(),
+                    request_body: endpoint
+                        .body()
+                        .and_then(|body| serde_json::to_value(body).ok()),
+                    status,
+                    response_body: res_str.clone(),
+                },
+                &[&self.auth_token, &self.auth_token_encoded],
+            );
+        }
+
+        Self::read_response(&path, status, res_str)
+    }
+}
+
+impl AsyncGameClient {
+    pub fn new(config: &Config) -> Self {
+        Self {
+            settings: ClientSettings::new(config),
+            skin_mode: config.skin_mode,
+            group_phrase: config.group_phrase.clone(),
+            lru_half_life: config.lru_half_life(),
+            rarity_weights: config.rarity_weights.clone(),
+            chroma_mode: config.chroma_mode,
+            chroma_chance: config.chroma_chance,
+            shuffle_bags_path: Some(config.shuffle_bags_path()),
+            skin_filters_path: Some(config.skin_filters_path()),
+            play_history_path: Some(config.play_history_path()),
+            guard: MutationGuard::new(config),
+            audit_log_path: Some(config.audit_log_path()),
+            ..Default::default()
+        }
+    }
+
+    // Picks with the recorded settings, state and seeds, the local files
+    // are neither read nor written
+    pub fn replay(fixture: Fixture) -> Self {
+        let config = fixture.config.clone().unwrap_or_default();
+        Self {
+            shuffle_bags_path: None,
+            skin_filters_path: None,
+            play_history_path: None,
+            audit_log_path: None,
+            replay: Some(Arc::new(Replay::new(fixture))),
+            ..Self::new(&config)
+        }
+    }
+
+    pub fn set_recorder(&mut self, recorder: Arc<Recorder>) {
+        self.api.set_recorder(recorder);
+    }
+
+    // Rejected credentials fail with Unauthorized, which also authenticates
+    pub async fn health(&self) -> Result<ClientHealth, LcuError> {
+        match self.request(&endpoints::GameflowPhase).await {
+            Ok(phase) => Ok(ClientHealth::from_phase(phase)),
+            // The gameflow plugin is only up once an account is logged in
+            Err(e) if matches!(e.status_code(), Some(404 | 503)) => {
+                Ok(ClientHealth::NotLoggedIn)
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    // Riot ID when the account has one, the legacy display name otherwise
+    pub async fn summoner_name(&self) -> Result<String, LcuError> {
+        let summoner = self.request(&endpoints::CurrentSummoner).await?;
+        Ok(match summoner.game_name.is_empty() {
+            true => summoner.display_name,
+            false => format!("{}#{}", summoner.game_name, summoner.tag_line),
+        })
+    }
+
+    async fn ensure_logged_in(&self) -> Result<(), LcuError> {
+        match self.health().await {
+            Ok(ClientHealth::NotLoggedIn) => Err(LcuError::NotLoggedIn),
+            Ok(_) => Ok(()),
+            // A rejected certificate is a Tls error, the client is there
+            Err(LcuError::Request(e)) if !e.is_timeout() => {
+                Err(LcuError::ClientNotRunning)
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    pub fn subscribe_events(&self) -> Result<EventSocket, Box<dyn StdError>> {
+        events::connect(
+            &self.api.port,
+            &self.api.auth_token_encoded,
+            self.settings.tls_connector()?,
+            self.settings.connect_timeout,
+            self.settings.request_timeout,
+        )
+    }
+
+    // Rebuilds the http client for freshly discovered credentials
+    pub fn connect(
+        &mut self,
+        source: &'static str,
+        port_and_token: PortAndToken,
+    ) -> Result<(), Box<dyn StdError>> {
+        println!(
+            "Credentials for port {} found via {}",
+            port_and_token.port, source
         );
-        let res = self.client.get(url).send()?;
-        let res_str = res.text()?;
-        let result: models::CurrentSummonerAccountAndSummonerIds =
-            serde_json::from_str(&res_str)?;
-        Ok(result)
+        self.source = Some(source);
+        self.api.connect(&self.settings, port_and_token)?;
+
+        // Development Debugging
+        #[cfg(debug_assertions)]
+        println!(
+            "Source {:?}\nPort {}\nAuth {}\nEncoded {}",
+            self.source,
+            self.api.port,
+            self.api.auth_token,
+            self.api.auth_token_encoded
+        );
+        Ok(())
     }
 
-    pub fn call_champions_v1_inventories_summonerid_champions_championid_skins(
+    // Anything but a GET has to pass the guard and is audited either way
+    pub async fn request<E: Endpoint>(
         &self,
-        summoner_id: i64,
-        champion_id: i64,
-    ) -> Result<Vec<models::ChampionsCollectionsChampionSkin>, Box<dyn StdError>>
-    {
-        let url = self.build_url(&format!(
-            "lol-champions/v1/inventories/{}/champions/{}/skins",
-            summoner_id, champion_id
-        ));
-        let res = self.client.get(url).send()?;
-        let res_str = res.text()?;
-        let result: Vec<models::ChampionsCollectionsChampionSkin> =
-            serde_json::from_str(&res_str)?;
-        Ok(result)
-    }
-
-    pub fn call_champ_select_v1_session(
-        &self,
-    ) -> Result<models::ChampSelectSession, Box<dyn StdError>> {
-        let url = self.build_url("lol-champ-select/v1/session");
-        let res = self.client.get(url).send()?;
-        let res_str = res.text()?;
-        let result: models::ChampSelectSession =
-            serde_json::from_str(&res_str)?;
-        Ok(result)
+        endpoint: &E,
+    ) -> Result<E::Response, LcuError> {
+        if E::METHOD == Method::GET {
+            return self.send(endpoint).await;
+        }
+
+        let mut entry = AuditEntry {
+            time_ms: scheduler::epoch_ms(),
+            port: self.api.port.clone(),
+            method: E::METHOD.to_string(),
+            path: endpoint.path(),
+            body: endpoint
+                .body()
+                .and_then(|body| serde_json::to_value(body).ok()),
+            ..Default::default()
+        };
+        let session = self
+            .send(&endpoints::Session)
+            .await
+            .map_err(LcuError::or_not_in_champ_select);
+        let res = match session {
+            Ok(session) => {
+                entry.queue_id = Some(session.queue_id);
+                entry.is_custom_game = Some(session.is_custom_game);
+                entry.phase = Some(session.timer.phase.clone());
+                match self.guard.check(&session) {
+                    Ok(()) => self.send(endpoint).await,
+                    Err(reason) => Err(LcuError::Blocked(reason)),
+                }
+            }
+            Err(e) => Err(e),
+        };
+
+        entry.outcome = match &res {
+            Ok(_) => "sent".to_string(),
+            Err(e) => e.to_string(),
+        };
+        if let Some(path) = &self.audit_log_path {
+            if let Err(e) = audit::append(path, &entry) {
+                println!("Failed writing audit log: {}", e);
+            }
+        }
+        res
     }
 
-    pub fn call_champ_select_v1_session_my_selection(
+    async fn send<E: Endpoint>(
         &self,
-        selected_skin_id: i64,
-    ) -> Result<(), Box<dyn StdError>> {
-        let url = self.build_url("lol-champ-select/v1/session/my-selection");
-        let body_str = serde_json::to_string(
-            &models::ChampSelectChampSelectMySelection {
-                selected_skin_id,
-                spell1_id: None,
-                spell2_id: None,
-                ward_skin_id: None,
+        endpoint: &E,
+    ) -> Result<E::Response, LcuError> {
+        match &self.replay {
+            Some(replay) => {
+                let path = endpoint.path();
+                let (status, res_str) =
+                    replay.respond(E::METHOD.as_str(), &path);
+                ApiClient::read_response(&path, status, res_str)
+            }
+            None => self.api.send(endpoint).await,
+        }
+    }
+
+    // Pickable skins of the current champion, favorites and blacklist applied
+    async fn skin_candidates(&self) -> Result<SkinCandidates, LcuError> {
+        self.ensure_logged_in().await?;
+
+        let (summoner_id, skin_ids, current_champ) = tokio::try_join!(
+            self.request(&endpoints::CurrentSummonerIds),
+            async {
+                self.request(&endpoints::PickableSkinIds)
+                    .await
+                    .map_err(LcuError::or_not_in_champ_select)
+            },
+            async {
+                self.request(&endpoints::CurrentChampion)
+                    .await
+                    .map_err(LcuError::or_not_in_champ_select)
             },
         )?;
-        self.client.patch(url).body(body_str).send()?;
-        Ok(())
-    }
 
-    pub fn set_skin(&mut self) -> Result<String, String> {
-        if !self.status() {
-            return Err("LeagueClient not found!".to_string());
+        if current_champ == 0 {
+            return Err(LcuError::ChampionNotPicked);
+        }
+
+        let skin_collection = self
+            .request(&endpoints::ChampionSkins {
+                summoner_id: summoner_id.summoner_id,
+                champion_id: current_champ,
+            })
+            .await?;
+        let champ_skins: Vec<&ChampionsCollectionsChampionSkin> =
+            skin_collection
+                .iter()
+                .filter(|skin| skin_ids.contains(&skin.id))
+                .collect();
+        if champ_skins.is_empty() {
+            return Err(LcuError::NoCandidates("skins"));
+        }
+
+        let filter = self.skin_filters()?.champion(current_champ);
+        let known: Vec<i64> = skin_collection.iter().map(|s| s.id).collect();
+        let allowed: Vec<i64> = filter
+            .apply(champ_skins, |skin| skin.id, &known)
+            .iter()
+            .map(|skin| skin.id)
+            .collect();
+        if allowed.is_empty() {
+            return Err(LcuError::FilteredOut("skins"));
         }
 
-        let summoner_id = self
-            .call_summoner_v1_current_summoner_account_and_summoner_ids()
-            .map_err(|e| {
-                dbg!(e);
-                "Failed getting summoner id!".to_string()
-            })?;
-
-        let skin_ids =
-            self.call_champ_select_v1_pickable_skin_ids().map_err(|e| {
-                dbg!(e);
-                "Not in champion select!".to_string()
-            })?;
-
-        let current_champ =
-            self.call_champ_select_v1_current_champion().map_err(|e| {
-                dbg!(e);
-                "Not in champion select!".to_string()
-            })?;
-
-        let champ_skin_ids: Vec<(i64, String)> = self
-            .call_champions_v1_inventories_summonerid_champions_championid_skins(
-                summoner_id.summoner_id,
-                current_champ,
-            )
-            .map_err(|e| {
-                dbg!(e);
-                "Champion not picked yet!".to_string()
-            })?
+        let chroma_ids = skin_collection
             .iter()
-            .filter(|skin| skin_ids.contains(&skin.id))
-            .map(|skin| (skin.id, skin.name.clone()))
+            .flat_map(|skin| skin.chromas.iter().map(|c| c.id))
             .collect();
+        Ok(SkinCandidates {
+            summoner_id: summoner_id.summoner_id,
+            champion_id: current_champ,
+            skins: skin_collection
+                .into_iter()
+                .filter(|skin| allowed.contains(&skin.id))
+                .collect(),
+            filter,
+            chroma_ids,
+        })
+    }
 
-        let Some((skin_id, skin_name)) =
-            champ_skin_ids.choose(&mut rand::rng())
-        else {
-            return Err("No skins available!".to_string());
+    fn pick_skin<'a, R: Rng + ?Sized>(
+        &self,
+        candidates: &'a SkinCandidates,
+        weight: &impl Fn(&ChampionsCollectionsChampionSkin) -> f64,
+        rng: &mut R,
+    ) -> Result<&'a ChampionsCollectionsChampionSkin, LcuError> {
+        let skins: Vec<&ChampionsCollectionsChampionSkin> =
+            candidates.skins.iter().collect();
+        let picked = match self.skin_mode {
+            SkinMode::Random | SkinMode::OfTheDay => {
+                selection::pick_skin(&skins, weight, rng)
+            }
+            SkinMode::ShuffleBag => {
+                // Reloaded every time, other clients may share the file.
+                // Nothing is saved until the selection went through
+                self.shuffle_bags()
+                    .bag(candidates.summoner_id, candidates.champion_id)
+                    .pick(&skins, weight, rng)
+            }
+            SkinMode::LeastRecent => {
+                let history = self.play_history();
+                let now = SystemTime::now();
+                let weight = |skin: &ChampionsCollectionsChampionSkin| {
+                    weight(skin)
+                        * history.recency(
+                            candidates.summoner_id,
+                            skin,
+                            now,
+                            self.lru_half_life,
+                        )
+                };
+                selection::pick_skin(&skins, &weight, rng)
+            }
         };
+        // Least recent odds never drop to zero, only the rarity weights can
+        picked.ok_or(LcuError::ZeroWeights("skins"))
+    }
 
-        self.call_champ_select_v1_session_my_selection(*skin_id)
-            .map_err(|e| {
-                dbg!(e);
-                "Failed changing skin!".to_string()
-            })?;
+    // Draws the selected skin from its bag, only once the client took it
+    fn take_from_bag(&self, candidates: &SkinCandidates, skin_id: i64) {
+        if self.skin_mode != SkinMode::ShuffleBag {
+            return;
+        }
+        let mut bags = self.shuffle_bags();
+        let ids: Vec<i64> =
+            candidates.skins.iter().map(|skin| skin.id).collect();
+        let bag = bags.bag(candidates.summoner_id, candidates.champion_id);
+        bag.reconcile(&ids);
+        bag.take(skin_id);
+        match (&self.replay, &self.shuffle_bags_path) {
+            (Some(replay), _) => {
+                replay.with_state(|state| state.shuffle_bags = bags)
+            }
+            (None, Some(path)) => {
+                if let Err(e) = bags.save(path) {
+                    println!("Failed saving shuffle bags: {}", e);
+                }
+            }
+            (None, None) => {}
+        }
+    }
 
-        Ok(skin_name.clone())
+    // Kept in every mode, so least_recent knows the past when switched to.
+    // A chroma counts for the skin it belongs to
+    pub async fn record_played(&self, played: &Played) -> Result<(), LcuError> {
+        let skin_collection = self
+            .request(&endpoints::ChampionSkins {
+                summoner_id: played.summoner_id,
+                champion_id: played.champion_id,
+            })
+            .await?;
+        let skin_id = skin_collection
+            .iter()
+            .find(|skin| {
+                skin.id == played.skin_id
+                    || skin.chromas.iter().any(|chr| chr.id == played.skin_id)
+            })
+            .map_or(played.skin_id, |skin| skin.id);
+
+        let mut history = self.play_history();
+        history.record(played.summoner_id, skin_id, SystemTime::now());
+        match (&self.replay, &self.play_history_path) {
+            (Some(replay), _) => {
+                replay.with_state(|state| state.play_history = history)
+            }
+            (None, Some(path)) => {
+                if let Err(e) = history.save(path) {
+                    println!("Failed saving play history: {}", e);
+                }
+            }
+            (None, None) => {}
+        }
+        Ok(())
     }
 
-    pub fn set_chroma(&self) -> Result<ChromaTextAndColor, String> {
-        if !self.status() {
-            return Err("LeagueClient not found!".to_string());
+    // Replays read the recorded state instead of the files
+    fn skin_filters(&self) -> Result<SkinFilters, LcuError> {
+        match (&self.replay, &self.skin_filters_path) {
+            (Some(replay), _) => {
+                Ok(replay.with_state(|state| state.skin_filters.clone()))
+            }
+            (None, Some(path)) => SkinFilters::load(path),
+            (None, None) => Ok(SkinFilters::default()),
         }
+    }
 
-        let summoner_id = self
-            .call_summoner_v1_current_summoner_account_and_summoner_ids()
-            .map_err(|e| {
-                dbg!(e);
-                "Failed getting summoner id!".to_string()
-            })?;
+    fn shuffle_bags(&self) -> ShuffleBags {
+        match (&self.replay, &self.shuffle_bags_path) {
+            (Some(replay), _) => {
+                replay.with_state(|state| state.shuffle_bags.clone())
+            }
+            (None, Some(path)) => ShuffleBags::load(path),
+            (None, None) => ShuffleBags::default(),
+        }
+    }
 
-        let champ_select =
-            self.call_champ_select_v1_session().map_err(|e| {
-                dbg!(e);
-                "Skin not picked!".to_string()
-            })?;
+    fn play_history(&self) -> PlayHistory {
+        match (&self.replay, &self.play_history_path) {
+            (Some(replay), _) => {
+                replay.with_state(|state| state.play_history.clone())
+            }
+            (None, Some(path)) => PlayHistory::load(path),
+            (None, None) => PlayHistory::default(),
+        }
+    }
+
+    // Every pick runs on a seeded rng so it can be reproduced, the skin of
+    // the day's seed comes from the date instead of chance
+    fn seed(&self) -> u64 {
+        let replayed =
+            self.replay.as_ref().and_then(|replay| replay.next_seed());
+        let seed = replayed.unwrap_or_else(|| match self.skin_mode {
+            SkinMode::OfTheDay => selection::daily_seed(
+                selection::utc_day(SystemTime::now()),
+                &self.group_phrase,
+            ),
+            SkinMode::Random | SkinMode::ShuffleBag | SkinMode::LeastRecent => {
+                rand::rng().random()
+            }
+        });
+        if let Some(recorder) = &self.api.recorder {
+            recorder.record_seed(seed);
+        }
+        seed
+    }
+
+    pub async fn set_skin(&self) -> Result<RollOutcome, LcuError> {
+        let candidates = self.skin_candidates().await?;
+        let weight = |skin: &ChampionsCollectionsChampionSkin| {
+            self.rarity_weights.skin_weight(skin)
+        };
+        let seed = self.seed();
+        let mut rng = selection::seeded_rng(seed);
+        let skin = self.pick_skin(&candidates, &weight, &mut rng)?;
+
+        self.request(&endpoints::MySelection::skin(skin.id)).await?;
+        self.take_from_bag(&candidates, skin.id);
+
+        Ok(RollOutcome {
+            seed,
+            skin_id: skin.id,
+            skin_name: skin.name.clone(),
+            chroma: None,
+        })
+    }
+
+    fn pick_roll<'a, R: Rng + ?Sized>(
+        &self,
+        candidates: &'a SkinCandidates,
+        rng: &mut R,
+    ) -> Result<
+        (&'a ChampionsCollectionsChampionSkin, Option<&'a Chroma>),
+        LcuError,
+    > {
+        let weight = |skin: &ChampionsCollectionsChampionSkin| {
+            let chromas = candidates.chromas(skin).len();
+            self.rarity_weights.skin_weight(skin)
+                * selection::roll_weight(self.chroma_mode, chromas)
+        };
+        let skin = self.pick_skin(candidates, &weight, rng)?;
+        let chroma = selection::pick_chroma(
+            &candidates.chromas(skin),
+            self.chroma_mode,
+            self.chroma_chance,
+            rng,
+        );
+        Ok((skin, chroma))
+    }
+
+    // Skin and chroma in one go, with a single selection request. The
+    // outcome is complete before it's sent, an error selected nothing
+    pub async fn roll(&self) -> Result<RollOutcome, LcuError> {
+        let candidates = self.skin_candidates().await?;
+        let seed = self.seed();
+        let (skin, chroma) =
+            self.pick_roll(&candidates, &mut selection::seeded_rng(seed))?;
+        let outcome = RollOutcome {
+            seed,
+            skin_id: skin.id,
+            skin_name: skin.name.clone(),
+            chroma: chroma.map(chroma_outcome).transpose()?,
+        };
+
+        self.request(&endpoints::MySelection::skin(outcome.selected_id()))
+            .await?;
+        self.take_from_bag(&candidates, skin.id);
+        Ok(outcome)
+    }
+
+    pub async fn set_chroma(&self) -> Result<ChromaTextAndColor, LcuError> {
+        self.ensure_logged_in().await?;
+
+        let (summoner_id, champ_select) = tokio::try_join!(
+            self.request(&endpoints::CurrentSummonerIds),
+            async {
+                self.request(&endpoints::Session)
+                    .await
+                    .map_err(LcuError::or_not_in_champ_select)
+            },
+        )?;
 
         let (selected_skin_id, champion_id) = champ_select
             .my_team
@@ -293,19 +757,15 @@ impl GameClient {
             .unwrap_or((0, 0));
 
         if champion_id == 0 {
-            return Err("Skin not picked!".to_string());
+            return Err(LcuError::ChampionNotPicked);
         }
 
         let skin_collection = self
-            .call_champions_v1_inventories_summonerid_champions_championid_skins(
-                summoner_id.summoner_id,
+            .request(&endpoints::ChampionSkins {
+                summoner_id: summoner_id.summoner_id,
                 champion_id,
-            )
-            .map_err(|e| {
-                dbg!(e);
-                "Not in champion select!".to_string()
-            })?;
-
+            })
+            .await?;
         let current_skin =
             skin_collection.iter().find(|s| s.id == selected_skin_id);
 
@@ -325,22 +785,123 @@ impl GameClient {
                     skin.chromas.iter().filter(|c| c.ownership.owned).collect();
             }
         }
+        if current_chromas.is_empty() {
+            return Err(LcuError::NoCandidates("chroma"));
+        }
 
-        let Some(chroma) = current_chromas.choose(&mut rand::rng()) else {
-            return Err("No chroma available!".to_string());
+        let known: Vec<i64> = skin_collection
+            .iter()
+            .flat_map(|skin| skin.chromas.iter().map(|c| c.id))
+            .collect();
+        let current_chromas = self.skin_filters()?.champion(champion_id).apply(
+            current_chromas,
+            |chroma| chroma.id,
+            &known,
+        );
+
+        let seed = self.seed();
+        let mut rng = selection::seeded_rng(seed);
+        let Some(chroma) = current_chromas.choose(&mut rng) else {
+            return Err(LcuError::FilteredOut("chroma"));
         };
 
-        self.call_champ_select_v1_session_my_selection(chroma.id)
-            .map_err(|e| {
-                dbg!(e);
-                "Failed setting chroma".to_string()
-            })?;
-
-        u32::from_str_radix(chroma.colors[0].trim_start_matches('#'), 16)
-            .map(|color| ("Chroma Randomized!".to_string(), color))
-            .map_err(|e| {
-                dbg!(e);
-                "Invalid color format".to_string()
-            })
+        // Parsed first, a chroma that can't be shown isn't selected either
+        let color = chroma.colors.first().cloned().unwrap_or_default();
+        let color = selection::parse_color(&color)
+            .ok_or(LcuError::InvalidColor(color))?;
+
+        self.request(&endpoints::MySelection::skin(chroma.id))
+            .await?;
+
+        Ok(ChromaTextAndColor {
+            seed,
+            text: "Chroma Randomized!".to_string(),
+            color,
+        })
+    }
+}
+
+fn chroma_outcome(chroma: &Chroma) -> Result<ChromaOutcome, LcuError> {
+    let colors = chroma
+        .colors
+        .iter()
+        .map(|color| {
+            selection::parse_color(color)
+                .ok_or_else(|| LcuError::InvalidColor(color.clone()))
+        })
+        .collect::<Result<_, _>>()?;
+    Ok(ChromaOutcome {
+        id: chroma.id,
+        name: chroma.name.clone(),
+        colors,
+    })
+}
+
+impl GameClient {
+    pub fn new(config: &Config) -> Self {
+        Self::from_async(AsyncGameClient::new(config))
+    }
+
+    pub fn replay(fixture: Fixture) -> Self {
+        Self::from_async(AsyncGameClient::replay(fixture))
+    }
+
+    pub fn set_recorder(&mut self, recorder: Arc<Recorder>) {
+        self.inner.set_recorder(recorder);
+    }
+
+    pub fn from_async(inner: AsyncGameClient) -> Self {
+        let runtime = runtime::Builder::new_multi_thread()
+            .worker_threads(2)
+            .enable_all()
+            .build()
+            .expect("Failed building tokio runtime");
+        Self {
+            inner,
+            runtime: Arc::new(runtime),
+        }
+    }
+
+    pub fn connect(
+        &mut self,
+        source: &'static str,
+        port_and_token: PortAndToken,
+    ) -> Result<(), Box<dyn StdError>> {
+        self.inner.connect(source, port_and_token)
+    }
+
+    pub fn health(&self) -> Result<ClientHealth, LcuError> {
+        self.runtime.block_on(self.inner.health())
+    }
+
+    pub fn summoner_name(&self) -> Result<String, LcuError> {
+        self.runtime.block_on(self.inner.summoner_name())
+    }
+
+    pub fn subscribe_events(&self) -> Result<EventSocket, Box<dyn StdError>> {
+        self.inner.subscribe_events()
+    }
+
+    pub fn request<E: Endpoint>(
+        &self,
+        endpoint: &E,
+    ) -> Result<E::Response, LcuError> {
+        self.runtime.block_on(self.inner.request(endpoint))
+    }
+
+    pub fn set_skin(&self) -> Result<RollOutcome, LcuError> {
+        self.runtime.block_on(self.inner.set_skin())
+    }
+
+    pub fn set_chroma(&self) -> Result<ChromaTextAndColor, LcuError> {
+        self.runtime.block_on(self.inner.set_chroma())
+    }
+
+    pub fn roll(&self) -> Result<RollOutcome, LcuError> {
+        self.runtime.block_on(self.inner.roll())
+    }
+
+    pub fn record_played(&self, played: &Played) -> Result<(), LcuError> {
+        self.runtime.block_on(self.inner.record_played(played))
     }
 }
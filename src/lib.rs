@@ -0,0 +1,19 @@
+pub mod audit;
+pub mod auto;
+pub mod config;
+pub mod connection;
+pub mod discovery;
+pub mod endpoints;
+pub mod error;
+pub mod events;
+pub mod filters;
+pub mod fixtures;
+pub mod guard;
+pub mod history;
+pub mod lcu;
+pub mod models;
+pub mod registry;
+pub mod riotclient;
+pub mod scheduler;
+pub mod selection;
+pub mod shufflebag;
@@ -10,28 +10,173 @@ use fltk::{
     frame::Frame,
     group::{Column, Flex},
     image::PngImage,
+    menu::Choice,
     prelude::*,
     window::Window,
 };
 use fltk_theme::{color_themes, widget_themes, ColorTheme};
+#[cfg(windows)]
 use std::ffi::c_void;
+#[cfg(windows)]
 use windows_sys::Win32::Foundation::HWND;
+#[cfg(windows)]
 use windows_sys::Win32::Graphics::Dwm::{
     DwmSetWindowAttribute, DWMWA_USE_IMMERSIVE_DARK_MODE,
 };
 
-use lcu::GameClient;
+use lol_skin_randomizer::auto::{AutoRandomizer, Due};
+use lol_skin_randomizer::config::Config;
+use lol_skin_randomizer::connection::{self, Backoff, ConnectionState};
+use lol_skin_randomizer::error::LcuError;
+use lol_skin_randomizer::events::LcuEvent;
+use lol_skin_randomizer::history::{PlayTracker, Played};
+use lol_skin_randomizer::lcu::ClientHealth;
+use lol_skin_randomizer::registry::{
+    ClientInfo, ClientRegistry, Outcomes, Target,
+};
+use lol_skin_randomizer::riotclient::RiotClientStatus;
+use lol_skin_randomizer::scheduler;
 
+use std::cell::RefCell;
+use std::fmt;
+use std::rc::Rc;
 use std::sync::{Arc, Mutex};
 use std::thread;
-
-mod lcu;
-mod models;
+use std::time::Duration;
 
 enum ChannelMsg {
     Text(String),
     ChromaColor(u32),
-    ClientStatus(bool),
+    ClientStatus(ConnectionState),
+    Clients(Vec<ClientInfo>),
+    RiotClient(RiotClientStatus),
+    Event(LcuEvent),
+}
+
+// Picks up clients started while another one is already connected
+const CLIENT_REFRESH_INTERVAL: Duration = Duration::from_secs(10);
+const AUTO_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+// Short text for the label, the full error is printed to the console
+fn error_message(e: &LcuError) -> String {
+    match e {
+        LcuError::Request(e) if e.is_timeout() => {
+            "LeagueClient not responding!".to_string()
+        }
+        LcuError::HttpStatus { .. }
+        | LcuError::Decode { .. }
+        | LcuError::Request(_) => "Request to LeagueClient failed!".to_string(),
+        LcuError::Tls(_) => "LeagueClient certificate not trusted!".to_string(),
+        LcuError::Api { error, .. } => format!("Refused: {}", error.message),
+        LcuError::InvalidColor(_) => "Invalid color format".to_string(),
+        e => e.to_string(),
+    }
+}
+
+// Statusbar text while no League client is connected
+fn offline_label(riot_client: &RiotClientStatus) -> &'static str {
+    match riot_client {
+        RiotClientStatus::NotRunning => " Client",
+        RiotClientStatus::Open => " Riot Client (League not launched)",
+        RiotClientStatus::LeagueLaunched => " Riot Client (League starting)",
+    }
+}
+
+// One line per client when the buttons target several of them
+fn outcomes_text<T>(
+    outcomes: &Outcomes<T>,
+    text: impl Fn(&T) -> String,
+) -> String {
+    let lines: Vec<String> = outcomes
+        .iter()
+        .map(|(info, res)| {
+            let line = match res {
+                Ok(value) => text(value),
+                Err(e) => {
+                    println!("{}: {}", info.port, e);
+                    error_message(e)
+                }
+            };
+            match (outcomes.len(), &info.summoner) {
+                (1, _) | (_, None) => line,
+                (_, Some(summoner)) => format!("{}: {}", summoner, line),
+            }
+        })
+        .collect();
+    lines.join("\n")
+}
+
+// The seed reproduces the pick, e.g. when reporting a bug
+fn with_seed(text: impl fmt::Display, seed: u64) -> String {
+    format!("{} (seed {})", text, seed)
+}
+
+// Off the event thread, which must keep reading
+fn spawn_auto_randomize(
+    registry: Arc<Mutex<ClientRegistry>>,
+    auto: Arc<Mutex<AutoRandomizer>>,
+    due: Due,
+    s: app::Sender<ChannelMsg>,
+) {
+    thread::spawn(move || {
+        let outcomes = match registry.lock() {
+            Ok(registry) => registry.auto_randomize(&due.port, due.mode),
+            Err(e) => {
+                dbg!(e);
+                return;
+            }
+        };
+        let selected = match outcomes.as_deref() {
+            Ok([(_, Ok(outcome))]) => Some(outcome.selected_id()),
+            _ => None,
+        };
+        if let Ok(mut auto) = auto.lock() {
+            auto.on_randomized(&due.port, selected);
+        }
+        match outcomes {
+            Ok(outcomes) => {
+                let text = outcomes_text(&outcomes, |o| with_seed(o, o.seed));
+                s.send(ChannelMsg::Text(text));
+            }
+            Err(e) => {
+                println!("{}", e);
+                s.send(ChannelMsg::Text(error_message(&e)));
+            }
+        }
+    });
+}
+
+// Also off the event thread, the skin collection is asked which skin a
+// chroma belongs to
+fn spawn_record_played(
+    registry: Arc<Mutex<ClientRegistry>>,
+    port: String,
+    played: Played,
+) {
+    thread::spawn(move || match registry.lock() {
+        Ok(registry) => {
+            if let Err(e) = registry.record_played(&port, &played) {
+                println!("Failed recording the played skin: {}", e);
+            }
+        }
+        Err(e) => {
+            dbg!(e);
+        }
+    });
+}
+
+// Menu labels treat these as submenu, shortcut and divider markers
+fn menu_label(label: &str) -> String {
+    label
+        .replace('|', " ")
+        .chars()
+        .fold(String::new(), |mut out, c| {
+            if matches!(c, '/' | '&' | '_' | '\\') {
+                out.push('\\');
+            }
+            out.push(c);
+            out
+        })
 }
 
 fn main() {
@@ -52,15 +197,17 @@ fn main() {
 
     // Inizialize lcu client and channel for updating the gui
     let (s, r) = app::channel::<ChannelMsg>();
-    let client = Arc::new(Mutex::new(GameClient::new()));
-    let (c1, c2, c3) = (client.clone(), client.clone(), client.clone());
+    let mut config = Config::load();
+    config.apply_args(std::env::args().skip(1));
+    let registry = Arc::new(Mutex::new(ClientRegistry::new(&config)));
+    let clients: Rc<RefCell<Vec<ClientInfo>>> = Rc::default();
 
     let app = app::App::default();
     let theme = ColorTheme::new(color_themes::BLACK_THEME);
     theme.apply();
 
     let mut win = Window::default()
-        .with_size(260, 125)
+        .with_size(330, 155)
         .with_label("Skin Randomizer");
 
     win.set_icon(Some(icon_app));
@@ -85,15 +232,19 @@ fn main() {
     btn_skin.set_image(Some(icon_skin));
     btn_skin.set_align(Align::ImageNextToText);
     btn_skin.set_frame(widget_themes::OS_BUTTON_UP_BOX);
+    let skin_registry = registry.clone();
     btn_skin.set_callback(move |_| {
-        let c1 = c1.clone();
-        thread::spawn(move || match c1.lock() {
-            Ok(mut g) => match g.set_skin() {
-                Ok(skin_name) => {
-                    s.send(ChannelMsg::Text(skin_name));
+        let registry = skin_registry.clone();
+        thread::spawn(move || match registry.lock() {
+            Ok(registry) => match registry.set_skin() {
+                Ok(outcomes) => {
+                    let text =
+                        outcomes_text(&outcomes, |o| with_seed(o, o.seed));
+                    s.send(ChannelMsg::Text(text));
                 }
                 Err(e) => {
-                    s.send(ChannelMsg::Text(e));
+                    println!("{}", e);
+                    s.send(ChannelMsg::Text(error_message(&e)));
                 }
             },
             Err(e) => {
@@ -109,16 +260,58 @@ fn main() {
     btn_chroma.set_image(Some(icon_chroma));
     btn_chroma.set_align(Align::ImageNextToText);
     btn_chroma.set_frame(widget_themes::OS_BUTTON_UP_BOX);
+    let chroma_registry = registry.clone();
     btn_chroma.set_callback(move |_| {
-        let c2 = c2.clone();
-        thread::spawn(move || match c2.lock() {
-            Ok(g) => match g.set_chroma() {
-                Ok(chroma) => {
-                    s.send(ChannelMsg::Text(chroma.0));
-                    s.send(ChannelMsg::ChromaColor(chroma.1));
+        let registry = chroma_registry.clone();
+        thread::spawn(move || match registry.lock() {
+            Ok(registry) => match registry.set_chroma() {
+                Ok(outcomes) => {
+                    let text = outcomes_text(&outcomes, |c| {
+                        with_seed(&c.text, c.seed)
+                    });
+                    s.send(ChannelMsg::Text(text));
+                    // Only a single chroma can color the label
+                    if let [(_, Ok(chroma))] = outcomes.as_slice() {
+                        s.send(ChannelMsg::ChromaColor(chroma.color));
+                    }
                 }
                 Err(e) => {
-                    s.send(ChannelMsg::Text(e));
+                    println!("{}", e);
+                    s.send(ChannelMsg::Text(error_message(&e)));
+                }
+            },
+            Err(e) => {
+                dbg!(e);
+            }
+        });
+    });
+
+    let mut btn_roll = Button::default().with_label("Both");
+    btn_roll.set_label_font(Font::Helvetica);
+    btn_roll.set_label_size(16);
+    btn_roll.set_color(Color::Dark2);
+    btn_roll.set_frame(widget_themes::OS_BUTTON_UP_BOX);
+    let roll_registry = registry.clone();
+    btn_roll.set_callback(move |_| {
+        let registry = roll_registry.clone();
+        thread::spawn(move || match registry.lock() {
+            Ok(registry) => match registry.roll() {
+                Ok(outcomes) => {
+                    let text =
+                        outcomes_text(&outcomes, |o| with_seed(o, o.seed));
+                    s.send(ChannelMsg::Text(text));
+                    let color = match outcomes.as_slice() {
+                        [(_, Ok(outcome))] => outcome.chroma.as_ref(),
+                        _ => None,
+                    }
+                    .and_then(|chroma| chroma.colors.first());
+                    if let Some(color) = color {
+                        s.send(ChannelMsg::ChromaColor(*color));
+                    }
+                }
+                Err(e) => {
+                    println!("{}", e);
+                    s.send(ChannelMsg::Text(error_message(&e)));
                 }
             },
             Err(e) => {
@@ -129,14 +322,37 @@ fn main() {
 
     group_btns.end();
 
+    let mut client_choice = Choice::default();
+    client_choice.set_label_font(Font::Helvetica);
+    client_choice.set_text_size(12);
+    client_choice.set_color(Color::Dark2);
+    client_choice.add_choice("All clients");
+    client_choice.set_value(0);
+    let choice_clients = clients.clone();
+    let choice_registry = registry.clone();
+    client_choice.set_callback(move |choice| {
+        let target = usize::try_from(choice.value() - 1)
+            .ok()
+            .and_then(|i| choice_clients.borrow().get(i).cloned())
+            .map_or(Target::All, |info| Target::Port(info.port));
+        let registry = choice_registry.clone();
+        thread::spawn(move || match registry.lock() {
+            Ok(mut registry) => registry.set_target(target),
+            Err(e) => {
+                dbg!(e);
+            }
+        });
+    });
+
     let mut statusbar = Frame::default().with_label(" Client");
     statusbar.set_label_font(Font::HelveticaItalic);
     statusbar.set_align(Align::Inside | Align::Left | Align::ImageNextToText);
     statusbar.set_label_size(12);
-    statusbar.set_image(Some(icon_status_grey));
+    statusbar.set_image(Some(icon_status_grey.clone()));
 
     column.fixed(&text, 40);
     column.fixed(&group_btns, 40);
+    column.fixed(&client_choice, 25);
     column.fixed(&statusbar, 15);
     column.end();
 
@@ -145,7 +361,9 @@ fn main() {
 
     // Using the win32 api to make the window title bar dark, since fltk doesnt support it
     // Get the window handle (HWND)
+    #[cfg(windows)]
     let hwnd = win.raw_handle() as HWND;
+    #[cfg(windows)]
     if hwnd != 0 {
         let use_dark_mode: i32 = 1;
         unsafe {
@@ -159,26 +377,94 @@ fn main() {
         }
     }
 
-    // Background thread to continuously check if the league client is running
-    let check_client_status = move |handle| {
-        let c3 = c3.clone();
-        thread::spawn(move || {
-            if let Ok(mut c3) = c3.lock() {
-                match c3.status() {
-                    true => s.send(ChannelMsg::ClientStatus(true)),
-                    false => match c3.retry() {
-                        Ok(_) => s.send(ChannelMsg::ClientStatus(true)),
-                        Err(e) => {
-                            dbg!(e);
-                            s.send(ChannelMsg::ClientStatus(false))
-                        }
-                    },
+    // Background thread keeping the client connected and forwarding its events
+    let auto = Arc::new(Mutex::new(AutoRandomizer::new(&config)));
+    let auto_events = auto.clone();
+    let events_registry = registry.clone();
+    let mut plays = PlayTracker::default();
+    thread::spawn(move || {
+        connection::run(
+            &events_registry,
+            Backoff::default(),
+            |state| s.send(ChannelMsg::ClientStatus(state)),
+            |list| s.send(ChannelMsg::Clients(list)),
+            |port, event| {
+                if let LcuEvent::ChampSelectSession(session) = &event {
+                    let due = auto_events.lock().ok().and_then(|mut auto| {
+                        auto.on_session(
+                            port,
+                            session.as_deref(),
+                            scheduler::epoch_ms(),
+                        )
+                    });
+                    match due {
+                        Some(Ok(due)) => spawn_auto_randomize(
+                            events_registry.clone(),
+                            auto_events.clone(),
+                            due,
+                            s,
+                        ),
+                        Some(Err(too_late)) => println!("{}", too_late),
+                        None => {}
+                    }
                 }
+                if let Some(played) = plays.on_event(&event) {
+                    spawn_record_played(
+                        events_registry.clone(),
+                        port.to_string(),
+                        played,
+                    );
+                }
+                s.send(ChannelMsg::Event(event))
+            },
+        )
+    });
+
+    // Timed randomizations come due in between session updates
+    let poll_registry = registry.clone();
+    thread::spawn(move || loop {
+        thread::sleep(AUTO_POLL_INTERVAL);
+        let due = match auto.lock() {
+            Ok(mut auto) => auto.poll(scheduler::epoch_ms()),
+            Err(e) => {
+                dbg!(e);
+                return;
             }
-        });
-        app::repeat_timeout3(1.0, handle);
-    };
-    app::add_timeout3(1.0, check_client_status);
+        };
+        match due {
+            Some(Ok(due)) => spawn_auto_randomize(
+                poll_registry.clone(),
+                auto.clone(),
+                due,
+                s,
+            ),
+            Some(Err(too_late)) => println!("{}", too_late),
+            None => {}
+        }
+    });
+
+    // The connection thread blocks on the primary client, so new ones and
+    // the Riot Client are only noticed by polling
+    let refresh_registry = registry.clone();
+    thread::spawn(move || loop {
+        let registry = &refresh_registry;
+        if let Err(e) = ClientRegistry::refresh_shared_if_connected(registry) {
+            dbg!(e);
+        }
+        match registry.lock() {
+            Ok(registry) => s.send(ChannelMsg::Clients(registry.list())),
+            Err(e) => {
+                dbg!(e);
+                return;
+            }
+        }
+        let riot_client = ClientRegistry::riot_client_status(registry);
+        s.send(ChannelMsg::RiotClient(riot_client));
+        thread::sleep(CLIENT_REFRESH_INTERVAL);
+    });
+
+    let mut connected = false;
+    let mut riot_client = RiotClientStatus::NotRunning;
 
     while app.wait() {
         if let Some(v) = r.recv() {
@@ -190,11 +476,65 @@ fn main() {
                 ChannelMsg::ChromaColor(c) => {
                     text.set_label_color(Color::from_hex(c));
                 }
-                ChannelMsg::ClientStatus(status) => {
-                    if status {
+                ChannelMsg::ClientStatus(state) => match state {
+                    ConnectionState::Connected(health) => {
+                        connected = true;
                         statusbar.set_image(Some(icon_status_green.clone()));
-                    } else {
+                        statusbar.set_label(&format!(" Client ({})", health));
+                        statusbar.set_tooltip("");
+                    }
+                    ConnectionState::Discovering
+                    | ConnectionState::Authenticating => {
+                        statusbar.set_image(Some(icon_status_grey.clone()));
+                    }
+                    ConnectionState::Disconnected => {
+                        connected = false;
+                        statusbar.set_image(Some(icon_status_red.clone()));
+                        statusbar.set_label(offline_label(&riot_client));
+                    }
+                    ConnectionState::Failed(reason) => {
+                        connected = false;
                         statusbar.set_image(Some(icon_status_red.clone()));
+                        statusbar.set_label(offline_label(&riot_client));
+                        statusbar.set_tooltip(&reason);
+                    }
+                },
+                ChannelMsg::RiotClient(status) => {
+                    riot_client = status;
+                    if !connected {
+                        statusbar.set_label(offline_label(&riot_client));
+                    }
+                }
+                ChannelMsg::Clients(list) => {
+                    if *clients.borrow() == list {
+                        continue;
+                    }
+                    // Keep the selected client if it's still running
+                    let selected = usize::try_from(client_choice.value() - 1)
+                        .ok()
+                        .and_then(|i| clients.borrow().get(i).cloned())
+                        .and_then(|info| {
+                            list.iter().position(|c| c.port == info.port)
+                        })
+                        .map_or(0, |i| i as i32 + 1);
+                    client_choice.clear();
+                    client_choice.add_choice("All clients");
+                    for info in &list {
+                        client_choice.add_choice(&menu_label(&info.label()));
+                    }
+                    client_choice.set_value(selected);
+                    *clients.borrow_mut() = list;
+                }
+                ChannelMsg::Event(LcuEvent::GameflowPhase(phase)) => {
+                    let health = ClientHealth::from_phase(phase);
+                    statusbar.set_label(&format!(" Client ({})", health));
+                }
+                ChannelMsg::Event(LcuEvent::ChampSelectSession(session)) => {
+                    if let Some(session) = session {
+                        statusbar.set_label(&format!(
+                            " Client ({})",
+                            session.timer.phase
+                        ));
                     }
                 }
             }
@@ -8,6 +8,41 @@ pub struct CurrentSummonerAccountAndSummonerIds {
     pub summoner_id: i64,
 }
 
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct CurrentSummoner {
+    pub display_name: String,
+    pub game_name: String,
+    pub tag_line: String,
+    pub summoner_id: i64,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ProductSessionSession {
+    pub patchline_id: String,
+    pub phase: String,
+    pub product_id: String,
+    pub version: String,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct LcuErrorBody {
+    pub error_code: String,
+    pub http_status: i64,
+    pub implementation_details: Value,
+    pub message: String,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct JsonApiEvent {
+    pub data: Value,
+    pub event_type: String,
+    pub uri: String,
+}
+
 #[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase", default)]
 pub struct ChampionsCollectionsChampionSkin {
@@ -80,7 +115,8 @@ pub struct ChampSelectSession {
     pub trades: Vec<Trade>,
     pub pick_order_swaps: Vec<Trade>,
     pub position_swaps: Vec<Trade>,
-    pub actions: Vec<serde_json::Value>,
+    // Turns of the draft, each holding the actions taken in parallel
+    pub actions: Vec<Vec<ChampSelectAction>>,
     pub bans: Bans,
     pub local_player_cell_id: i64,
     pub is_spectating: bool,
@@ -158,6 +194,20 @@ pub struct Player {
     pub pick_turn: i64,
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ChampSelectAction {
+    pub id: i64,
+    pub actor_cell_id: i64,
+    pub champion_id: i64,
+    pub completed: bool,
+    pub is_ally_action: bool,
+    pub is_in_progress: bool,
+    // "pick", "ban", "ten_bans_reveal", ...
+    #[serde(rename = "type")]
+    pub action_type: String,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, Default)]
 #[serde(rename_all = "camelCase", default)]
 pub struct Trade {
@@ -0,0 +1,392 @@
+use std::error::Error as StdError;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use crate::auto::AutoMode;
+use crate::config::Config;
+use crate::discovery::{self, CredentialSource, PortAndToken};
+use crate::error::LcuError;
+use crate::fixtures::{Fixture, Recorder};
+use crate::history::Played;
+use crate::lcu::{ChromaTextAndColor, GameClient};
+use crate::riotclient::{RiotClient, RiotClientStatus};
+use crate::selection::RollOutcome;
+
+const REPLAY_PORT: &str = "replay";
+
+// What each targeted client answered
+pub type Outcomes<T> = Vec<(ClientInfo, Result<T, LcuError>)>;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClientInfo {
+    pub port: String,
+    // None until the account is logged in
+    pub summoner: Option<String>,
+}
+
+impl ClientInfo {
+    pub fn label(&self) -> String {
+        match &self.summoner {
+            Some(summoner) => format!("{} ({})", summoner, self.port),
+            None => format!("Not logged in ({})", self.port),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ClientEntry {
+    pub info: ClientInfo,
+    pub credentials: PortAndToken,
+    pub client: GameClient,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Target {
+    All,
+    Port(String),
+}
+
+// Every running LeagueClientUx, the buttons act on the targeted ones
+#[derive(Debug)]
+pub struct ClientRegistry {
+    config: Config,
+    sources: Arc<Vec<Box<dyn CredentialSource>>>,
+    clients: Vec<ClientEntry>,
+    target: Target,
+    riot_client: Option<RiotClient>,
+    // Shared by every client, the Riot Client's included
+    recorder: Option<Arc<Recorder>>,
+    // A replayed fixture stands in for discovery entirely
+    replaying: bool,
+}
+
+impl ClientRegistry {
+    pub fn new(config: &Config) -> Self {
+        let mut registry =
+            Self::with_sources(config, discovery::sources_from_config(config));
+        if let Some(path) = &config.replay_fixture {
+            match Fixture::load(path) {
+                Ok(fixture) => registry.replay(fixture),
+                Err(e) => {
+                    println!("Failed loading {}: {}", path.display(), e)
+                }
+            }
+        }
+        registry
+    }
+
+    pub fn with_sources(
+        config: &Config,
+        sources: Vec<Box<dyn CredentialSource>>,
+    ) -> Self {
+        Self {
+            config: config.clone(),
+            sources: Arc::new(sources),
+            clients: Vec::new(),
+            target: Target::All,
+            riot_client: None,
+            recorder: config
+                .record_fixture
+                .as_deref()
+                .map(|path| Arc::new(Recorder::new(path, config))),
+            replaying: false,
+        }
+    }
+
+    fn replay(&mut self, fixture: Fixture) {
+        self.replaying = true;
+        self.clients = vec![ClientEntry {
+            info: ClientInfo {
+                port: REPLAY_PORT.to_string(),
+                summoner: Some("Replay".to_string()),
+            },
+            credentials: PortAndToken::default(),
+            client: GameClient::replay(fixture),
+        }];
+    }
+
+    pub fn refresh(&mut self) -> Result<(), Box<dyn StdError>> {
+        let Some(refresh) = self.begin_refresh() else {
+            return Ok(());
+        };
+        let found = refresh.run();
+        self.finish_refresh(found)
+    }
+
+    // Discovers and probes without holding the lock, which the buttons and
+    // the connection thread need meanwhile
+    pub fn refresh_shared(
+        registry: &Mutex<Self>,
+    ) -> Result<(), Box<dyn StdError>> {
+        let Some(refresh) = lock(registry)?.begin_refresh() else {
+            return Ok(());
+        };
+        let found = refresh.run();
+        lock(registry)?.finish_refresh(found)
+    }
+
+    // For the periodic refresh, which only looks for more clients while one
+    // is known, finding the first is left to the connection and its backoff
+    pub fn refresh_shared_if_connected(
+        registry: &Mutex<Self>,
+    ) -> Result<(), Box<dyn StdError>> {
+        if lock(registry)?.clients.is_empty() {
+            return Ok(());
+        }
+        Self::refresh_shared(registry)
+    }
+
+    // None while replaying, the fixture stands in for every client then
+    pub fn begin_refresh(&self) -> Option<Refresh> {
+        if self.replaying {
+            return None;
+        }
+        Some(Refresh {
+            config: self.config.clone(),
+            sources: self.sources.clone(),
+            recorder: self.recorder.clone(),
+            clients: self.clients.clone(),
+        })
+    }
+
+    pub fn finish_refresh(
+        &mut self,
+        found: Result<Vec<ClientEntry>, Box<dyn StdError>>,
+    ) -> Result<(), Box<dyn StdError>> {
+        self.clients = match found {
+            Ok(clients) => clients,
+            Err(e) => {
+                self.clients.clear();
+                return Err(e);
+            }
+        };
+
+        if let Target::Port(port) = &self.target {
+            if !self.clients.iter().any(|entry| &entry.info.port == port) {
+                self.target = Target::All;
+            }
+        }
+        if self.clients.is_empty() {
+            return Err("No LeagueClient is responding".into());
+        }
+        Ok(())
+    }
+
+    // Credentials come from a running League client when there is one, the
+    // Riot Client lockfile tells it's open when League isn't launched yet.
+    // Like refresh_shared it's asked without holding the lock
+    pub fn riot_client_status(registry: &Mutex<Self>) -> RiotClientStatus {
+        let Ok((config, recorder, from_league, known)) =
+            lock(registry).map(|registry| {
+                let from_league = registry.clients.iter().find_map(|entry| {
+                    let credentials =
+                        entry.credentials.riot_client.as_deref()?;
+                    Some(("leagueclientux", credentials.clone()))
+                });
+                (
+                    registry.config.clone(),
+                    registry.recorder.clone(),
+                    from_league,
+                    registry.riot_client.clone(),
+                )
+            })
+        else {
+            return RiotClientStatus::NotRunning;
+        };
+        let found = from_league.or_else(|| {
+            let path = config.riot_client_lockfile_path()?;
+            match discovery::read_riot_client_lockfile(&path) {
+                Ok(credentials) => Some(("riotclient-lockfile", credentials?)),
+                Err(e) => {
+                    println!("Riot Client lockfile: {}", e);
+                    None
+                }
+            }
+        });
+
+        let riot_client = found.and_then(|(source, credentials)| match known {
+            Some(known) if known.credentials == credentials => Some(known),
+            _ => RiotClient::connect(&config, recorder, source, credentials)
+                .inspect_err(|e| println!("Riot Client: {}", e))
+                .ok(),
+        });
+        let status = riot_client
+            .as_ref()
+            .map_or(RiotClientStatus::NotRunning, RiotClient::status);
+        if let Ok(mut registry) = lock(registry) {
+            registry.riot_client = riot_client;
+        }
+        status
+    }
+
+    pub fn list(&self) -> Vec<ClientInfo> {
+        self.clients
+            .iter()
+            .map(|entry| entry.info.clone())
+            .collect()
+    }
+
+    pub fn set_target(&mut self, target: Target) {
+        self.target = target;
+    }
+
+    // Events and the status come from the targeted client, or the first one
+    pub fn primary(&self) -> Option<&ClientEntry> {
+        let targeted = match &self.target {
+            Target::All => None,
+            Target::Port(port) => {
+                self.clients.iter().find(|entry| &entry.info.port == port)
+            }
+        };
+        targeted.or(self.clients.first())
+    }
+
+    fn targets(&self) -> Result<Vec<&ClientEntry>, LcuError> {
+        let targets: Vec<&ClientEntry> = self
+            .clients
+            .iter()
+            .filter(|entry| match &self.target {
+                Target::All => true,
+                Target::Port(port) => &entry.info.port == port,
+            })
+            .collect();
+        if targets.is_empty() {
+            return Err(LcuError::ClientNotRunning);
+        }
+        Ok(targets)
+    }
+
+    pub fn set_skin(&self) -> Result<Outcomes<RollOutcome>, LcuError> {
+        Ok(self
+            .targets()?
+            .into_iter()
+            .map(|entry| (entry.info.clone(), entry.client.set_skin()))
+            .collect())
+    }
+
+    pub fn roll(&self) -> Result<Outcomes<RollOutcome>, LcuError> {
+        Ok(self
+            .targets()?
+            .into_iter()
+            .map(|entry| (entry.info.clone(), entry.client.roll()))
+            .collect())
+    }
+
+    fn client(&self, port: &str) -> Result<&ClientEntry, LcuError> {
+        self.clients
+            .iter()
+            .find(|entry| entry.info.port == port)
+            .ok_or(LcuError::ClientNotRunning)
+    }
+
+    // Only the client whose session asked for it is randomized, which
+    // needn't be the primary one anymore by the time it's due
+    pub fn auto_randomize(
+        &self,
+        port: &str,
+        mode: AutoMode,
+    ) -> Result<Outcomes<RollOutcome>, LcuError> {
+        let entry = self.client(port)?;
+        let outcome = match mode {
+            AutoMode::Off => return Ok(Vec::new()),
+            AutoMode::Skin => entry.client.set_skin(),
+            AutoMode::Roll => entry.client.roll(),
+        };
+        Ok(vec![(entry.info.clone(), outcome)])
+    }
+
+    // Like auto_randomize, on the client the game was played on
+    pub fn record_played(
+        &self,
+        port: &str,
+        played: &Played,
+    ) -> Result<(), LcuError> {
+        self.client(port)?.client.record_played(played)
+    }
+
+    pub fn set_chroma(&self) -> Result<Outcomes<ChromaTextAndColor>, LcuError> {
+        Ok(self
+            .targets()?
+            .into_iter()
+            .map(|entry| (entry.info.clone(), entry.client.set_chroma()))
+            .collect())
+    }
+}
+
+// What a refresh needs, taken from the registry so discovery and probing can
+// run without holding its lock
+pub struct Refresh {
+    config: Config,
+    sources: Arc<Vec<Box<dyn CredentialSource>>>,
+    recorder: Option<Arc<Recorder>>,
+    clients: Vec<ClientEntry>,
+}
+
+impl Refresh {
+    // Keeps clients whose credentials didn't change, connects new ones and
+    // drops those that are gone or don't answer
+    pub fn run(mut self) -> Result<Vec<ClientEntry>, Box<dyn StdError>> {
+        let found =
+            discovery::discover_all(&self.sources, self.config.multi_client)?;
+
+        let mut clients = Vec::new();
+        for (source, credentials) in found {
+            let known = self
+                .clients
+                .iter()
+                .position(|entry| entry.credentials == credentials);
+            let mut entry = match known {
+                Some(i) => self.clients.swap_remove(i),
+                None => match self.connect(source, credentials) {
+                    Ok(entry) => entry,
+                    Err(e) => {
+                        println!("Skipping LCU via {}: {}", source, e);
+                        continue;
+                    }
+                },
+            };
+            if entry.info.summoner.is_none() {
+                entry.info.summoner = entry.client.summoner_name().ok();
+            }
+            clients.push(entry);
+        }
+        Ok(clients)
+    }
+
+    fn connect(
+        &self,
+        source: &'static str,
+        credentials: PortAndToken,
+    ) -> Result<ClientEntry, Box<dyn StdError>> {
+        let mut client = GameClient::new(&self.config);
+        if let Some(recorder) = &self.recorder {
+            client.set_recorder(recorder.clone());
+        }
+        client.connect(source, credentials.clone())?;
+        // Stale credentials from env or cli fail here rather than on a click
+        match client.health() {
+            Err(
+                e @ (LcuError::Request(_)
+                | LcuError::Tls(_)
+                | LcuError::Unauthorized),
+            ) => return Err(e.into()),
+            Err(e) => println!("LCU on port {}: {}", credentials.port, e),
+            Ok(_) => {}
+        }
+        Ok(ClientEntry {
+            info: ClientInfo {
+                port: credentials.port.clone(),
+                summoner: None,
+            },
+            credentials,
+            client,
+        })
+    }
+}
+
+fn lock(
+    registry: &Mutex<ClientRegistry>,
+) -> Result<MutexGuard<'_, ClientRegistry>, Box<dyn StdError>> {
+    registry
+        .lock()
+        .map_err(|_| "Client registry lock poisoned".into())
+}
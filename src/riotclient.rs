@@ -0,0 +1,88 @@
+use tokio::runtime::{self, Runtime};
+
+use std::error::Error as StdError;
+use std::sync::Arc;
+
+use crate::config::Config;
+use crate::discovery::PortAndToken;
+use crate::endpoints;
+use crate::error::LcuError;
+use crate::fixtures::Recorder;
+use crate::lcu::{ApiClient, ClientSettings};
+use crate::models::ProductSessionSession;
+
+const LEAGUE_PRODUCT_ID: &str = "league_of_legends";
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum RiotClientStatus {
+    NotRunning,
+    // Riot Client open without a League session
+    Open,
+    LeagueLaunched,
+}
+
+// The Riot Client api speaks the same auth as the LCU, only on its own port.
+// It's only ever read, so it needs neither the LCU client's guard nor more
+// than one thread to block on
+#[derive(Debug, Clone)]
+pub struct RiotClient {
+    pub credentials: PortAndToken,
+    api: ApiClient,
+    runtime: Arc<Runtime>,
+}
+
+impl RiotClient {
+    pub fn connect(
+        config: &Config,
+        recorder: Option<Arc<Recorder>>,
+        source: &'static str,
+        credentials: PortAndToken,
+    ) -> Result<Self, Box<dyn StdError>> {
+        println!(
+            "Riot Client credentials for port {} found via {}",
+            credentials.port, source
+        );
+        let mut api = ApiClient::default();
+        if let Some(recorder) = recorder {
+            api.set_recorder(recorder);
+        }
+        api.connect(&ClientSettings::new(config), credentials.clone())?;
+        let runtime = runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()?;
+        Ok(Self {
+            credentials,
+            api,
+            runtime: Arc::new(runtime),
+        })
+    }
+
+    pub fn product_sessions(
+        &self,
+    ) -> Result<Vec<ProductSessionSession>, LcuError> {
+        let sessions = self
+            .runtime
+            .block_on(self.api.send(&endpoints::ExternalSessions))?;
+        Ok(sessions.into_values().collect())
+    }
+
+    pub fn status(&self) -> RiotClientStatus {
+        match self.product_sessions() {
+            Ok(sessions) => {
+                let league = sessions
+                    .iter()
+                    .any(|session| session.product_id == LEAGUE_PRODUCT_ID);
+                match league {
+                    true => RiotClientStatus::LeagueLaunched,
+                    false => RiotClientStatus::Open,
+                }
+            }
+            Err(LcuError::Request(_)) => RiotClientStatus::NotRunning,
+            // Anything answering on the port is the Riot Client
+            Err(e) => {
+                println!("Riot Client: {}", e);
+                RiotClientStatus::Open
+            }
+        }
+    }
+}
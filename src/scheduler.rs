@@ -0,0 +1,235 @@
+use std::fmt;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::auto::AutoMode;
+use crate::models::Timer;
+
+const FINALIZATION_PHASE: &str = "FINALIZATION";
+
+// The real clock, callers pass the time in so a simulated one can be used
+pub fn epoch_ms() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since| since.as_millis() as i64)
+}
+
+// An armed randomization dropped because finalization ends before the
+// selection could land, for the caller to report
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TooLate;
+
+impl fmt::Display for TooLate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Finalization ends too soon, not randomizing")
+    }
+}
+
+// Holds an armed randomization back until shortly before finalization ends,
+// so the pick isn't revealed early or overwritten by hovering other skins
+#[derive(Debug, Clone, Default)]
+pub struct Scheduler {
+    lead_ms: i64,
+    // Time the selection request may take to land
+    margin_ms: i64,
+    armed: Option<AutoMode>,
+    fire_at_ms: Option<i64>,
+    // None while finalization has no end, e.g. in some custom games
+    phase_end_ms: Option<i64>,
+}
+
+impl Scheduler {
+    pub fn new(lead: Duration, margin: Duration) -> Self {
+        Self {
+            lead_ms: lead.as_millis() as i64,
+            margin_ms: margin.as_millis() as i64,
+            ..Default::default()
+        }
+    }
+
+    pub fn arm(&mut self, mode: AutoMode) {
+        self.armed = Some(mode);
+    }
+
+    pub fn cancel(&mut self) {
+        self.armed = None;
+    }
+
+    // The timer is as of internal_now_in_epoch_ms, which the event took a
+    // while to deliver, so the phase end is anchored there rather than at now
+    pub fn on_timer(&mut self, timer: &Timer, now_ms: i64) {
+        if timer.phase != FINALIZATION_PHASE {
+            self.fire_at_ms = None;
+            self.phase_end_ms = None;
+            return;
+        }
+        if timer.is_infinite {
+            self.fire_at_ms = Some(now_ms);
+            self.phase_end_ms = None;
+            return;
+        }
+        let sent_ms = match timer.internal_now_in_epoch_ms {
+            0 => now_ms,
+            sent_ms => sent_ms.min(now_ms),
+        };
+        let phase_end_ms = sent_ms + timer.adjusted_time_left_in_phase;
+        self.fire_at_ms = Some(phase_end_ms - self.lead_ms - self.margin_ms);
+        self.phase_end_ms = Some(phase_end_ms);
+    }
+
+    // The armed mode once it's due, dropped when it couldn't land in time
+    pub fn poll(&mut self, now_ms: i64) -> Option<Result<AutoMode, TooLate>> {
+        let fire_at_ms = self.fire_at_ms?;
+        if self.armed.is_none() || now_ms < fire_at_ms {
+            return None;
+        }
+        if let Some(phase_end_ms) = self.phase_end_ms {
+            if now_ms > phase_end_ms - self.margin_ms {
+                self.armed = None;
+                return Some(Err(TooLate));
+            }
+        }
+        self.armed.take().map(Ok)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Lead 3s and margin 500ms, like the defaults
+    const LEAD_MS: i64 = 3000;
+    const MARGIN_MS: i64 = 500;
+    // The simulated clock starts here
+    const START_MS: i64 = 1_700_000_000_000;
+
+    fn scheduler() -> Scheduler {
+        let mut scheduler = Scheduler::new(
+            Duration::from_millis(LEAD_MS as u64),
+            Duration::from_millis(MARGIN_MS as u64),
+        );
+        scheduler.arm(AutoMode::Skin);
+        scheduler
+    }
+
+    fn finalization(time_left_ms: i64, sent_ms: i64) -> Timer {
+        Timer {
+            phase: FINALIZATION_PHASE.to_string(),
+            adjusted_time_left_in_phase: time_left_ms,
+            internal_now_in_epoch_ms: sent_ms,
+            ..Default::default()
+        }
+    }
+
+    // The first time from START_MS on, in 10ms ticks, the armed mode fires
+    fn fires_at(scheduler: &mut Scheduler, until_ms: i64) -> Option<i64> {
+        (START_MS..=until_ms)
+            .step_by(10)
+            .find(|now_ms| matches!(scheduler.poll(*now_ms), Some(Ok(_))))
+    }
+
+    #[test]
+    fn fires_lead_and_margin_before_the_phase_ends() {
+        let mut scheduler = scheduler();
+        scheduler.on_timer(&finalization(10_000, START_MS), START_MS);
+
+        let fire_at_ms = START_MS + 10_000 - LEAD_MS - MARGIN_MS;
+        assert_eq!(
+            fires_at(&mut scheduler, START_MS + 10_000),
+            Some(fire_at_ms)
+        );
+        // Only once
+        assert_eq!(scheduler.poll(fire_at_ms + 10), None);
+    }
+
+    #[test]
+    fn anchors_the_phase_end_where_the_timer_was_sent() {
+        // Delivered 800ms after the client took the time
+        let mut scheduler = scheduler();
+        let sent_ms = START_MS - 800;
+        scheduler.on_timer(&finalization(10_000, sent_ms), START_MS);
+
+        let fire_at_ms = sent_ms + 10_000 - LEAD_MS - MARGIN_MS;
+        assert_eq!(
+            fires_at(&mut scheduler, START_MS + 10_000),
+            Some(fire_at_ms)
+        );
+    }
+
+    #[test]
+    fn ignores_a_send_time_ahead_of_the_clock() {
+        // The client's clock runs ahead, anchoring there would fire late
+        let mut scheduler = scheduler();
+        scheduler.on_timer(&finalization(10_000, START_MS + 2000), START_MS);
+
+        let fire_at_ms = START_MS + 10_000 - LEAD_MS - MARGIN_MS;
+        assert_eq!(
+            fires_at(&mut scheduler, START_MS + 10_000),
+            Some(fire_at_ms)
+        );
+    }
+
+    #[test]
+    fn missing_send_time_anchors_at_now() {
+        let mut scheduler = scheduler();
+        scheduler.on_timer(&finalization(10_000, 0), START_MS);
+
+        let fire_at_ms = START_MS + 10_000 - LEAD_MS - MARGIN_MS;
+        assert_eq!(
+            fires_at(&mut scheduler, START_MS + 10_000),
+            Some(fire_at_ms)
+        );
+    }
+
+    #[test]
+    fn infinite_finalization_fires_at_once() {
+        let mut scheduler = scheduler();
+        let timer = Timer {
+            is_infinite: true,
+            ..finalization(0, START_MS)
+        };
+        scheduler.on_timer(&timer, START_MS);
+
+        // Never too late without an end
+        assert_eq!(scheduler.poll(START_MS + 60_000), Some(Ok(AutoMode::Skin)));
+    }
+
+    #[test]
+    fn drops_what_can_no_longer_land() {
+        let mut scheduler = scheduler();
+        scheduler.on_timer(&finalization(10_000, START_MS), START_MS);
+
+        // Polled again only after the margin before the end started
+        let too_late_ms = START_MS + 10_000 - MARGIN_MS + 1;
+        assert_eq!(scheduler.poll(too_late_ms), Some(Err(TooLate)));
+        // Dropped for good, not fired on a later timer
+        scheduler.on_timer(&finalization(10_000, too_late_ms), too_late_ms);
+        assert_eq!(fires_at(&mut scheduler, too_late_ms + 10_000), None);
+    }
+
+    #[test]
+    fn waits_for_finalization() {
+        let mut scheduler = scheduler();
+        let ban_pick = Timer {
+            phase: "BAN_PICK".to_string(),
+            ..finalization(10_000, START_MS)
+        };
+        scheduler.on_timer(&ban_pick, START_MS);
+        assert_eq!(fires_at(&mut scheduler, START_MS + 20_000), None);
+
+        // Still armed once finalization starts
+        let start_ms = START_MS + 20_000;
+        scheduler.on_timer(&finalization(10_000, start_ms), start_ms);
+        assert_eq!(
+            scheduler.poll(start_ms + 10_000 - LEAD_MS - MARGIN_MS),
+            Some(Ok(AutoMode::Skin))
+        );
+    }
+
+    #[test]
+    fn cancelled_never_fires() {
+        let mut scheduler = scheduler();
+        scheduler.on_timer(&finalization(10_000, START_MS), START_MS);
+        scheduler.cancel();
+        assert_eq!(fires_at(&mut scheduler, START_MS + 10_000), None);
+    }
+}
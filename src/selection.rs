@@ -0,0 +1,437 @@
+use rand::prelude::IndexedRandom;
+use rand::rngs::ChaCha8Rng;
+use rand::{Rng, RngExt, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+use std::collections::HashMap;
+use std::fmt;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::models::{ChampionsCollectionsChampionSkin, Chroma};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Rarity {
+    Base,
+    // Regular skins without a gem
+    Standard,
+    Epic,
+    Legendary,
+    Mythic,
+    Ultimate,
+    Transcendent,
+    Exalted,
+}
+
+impl Rarity {
+    // The gem icon is named after the rarity, e.g. .../rarity-gem-icons/epic.png
+    pub fn of(skin: &ChampionsCollectionsChampionSkin) -> Self {
+        if skin.is_base {
+            return Self::Base;
+        }
+        if skin.skin_type.eq_ignore_ascii_case("ultimate") {
+            return Self::Ultimate;
+        }
+        let gem = Path::new(&skin.rarity_gem_path)
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+        match gem.as_str() {
+            "epic" => Self::Epic,
+            "legendary" => Self::Legendary,
+            "mythic" => Self::Mythic,
+            "ultimate" => Self::Ultimate,
+            "transcendent" => Self::Transcendent,
+            "exalted" => Self::Exalted,
+            _ => Self::Standard,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SkinMode {
+    #[default]
+    Random,
+    // Every skin once per champion before any repeats
+    ShuffleBag,
+    // Seeded by the date and group_phrase, so the same for a whole day and
+    // for everyone sharing the phrase
+    OfTheDay,
+    // Skins played lately are less likely, recovering over lru_half_life_days
+    LeastRecent,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChromaMode {
+    // Every chroma is a candidate on par with the skin it belongs to
+    Independent,
+    // Pick the skin first, then a chroma of it with chroma_chance
+    #[default]
+    SecondStage,
+}
+
+// Rarities missing from the map weigh 1, so the default is a uniform pick
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct RarityWeights(pub HashMap<Rarity, f64>);
+
+impl RarityWeights {
+    pub fn weight(&self, rarity: Rarity) -> f64 {
+        self.0.get(&rarity).copied().unwrap_or(1.0).max(0.0)
+    }
+
+    pub fn skin_weight(&self, skin: &ChampionsCollectionsChampionSkin) -> f64 {
+        self.weight(Rarity::of(skin))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChromaOutcome {
+    pub id: i64,
+    pub name: String,
+    pub colors: Vec<u32>,
+}
+
+// A combined roll, applied with a single selection
+#[derive(Debug, Clone, PartialEq)]
+pub struct RollOutcome {
+    // Replaying it with seeded_rng picks the same again
+    pub seed: u64,
+    pub skin_id: i64,
+    pub skin_name: String,
+    pub chroma: Option<ChromaOutcome>,
+}
+
+impl RollOutcome {
+    // What the session shows as selectedSkinId afterwards
+    pub fn selected_id(&self) -> i64 {
+        self.chroma
+            .as_ref()
+            .map_or(self.skin_id, |chroma| chroma.id)
+    }
+}
+
+impl fmt::Display for RollOutcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.chroma {
+            Some(chroma) => write!(f, "{} ({})", self.skin_name, chroma.name),
+            None => write!(f, "{}", self.skin_name),
+        }
+    }
+}
+
+const SECS_PER_DAY: u64 = 24 * 60 * 60;
+const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+// Days since the epoch in UTC, so friends in other time zones roll over
+// together
+pub fn utc_day(now: SystemTime) -> u64 {
+    now.duration_since(UNIX_EPOCH)
+        .map_or(0, |since| since.as_secs() / SECS_PER_DAY)
+}
+
+// FNV-1a rather than the std hasher, which may change between releases
+pub fn daily_seed(day: u64, group_phrase: &str) -> u64 {
+    day.to_le_bytes()
+        .iter()
+        .chain(group_phrase.trim().as_bytes())
+        .fold(FNV_OFFSET, |hash, byte| {
+            (hash ^ u64::from(*byte)).wrapping_mul(FNV_PRIME)
+        })
+}
+
+// StdRng may switch algorithms between rand releases, ChaCha8 keeps
+// recorded seeds picking the same skins
+pub fn seeded_rng(seed: u64) -> ChaCha8Rng {
+    ChaCha8Rng::seed_from_u64(seed)
+}
+
+// None when there is nothing to pick or every candidate weighs zero
+pub fn pick_skin<'a, R: Rng + ?Sized>(
+    skins: &[&'a ChampionsCollectionsChampionSkin],
+    weight: &impl Fn(&ChampionsCollectionsChampionSkin) -> f64,
+    rng: &mut R,
+) -> Option<&'a ChampionsCollectionsChampionSkin> {
+    skins
+        .choose_weighted(rng, |skin| weight(skin))
+        .ok()
+        .copied()
+}
+
+// Independent chromas make a skin as likely as all its candidates together
+pub fn roll_weight(mode: ChromaMode, chromas: usize) -> f64 {
+    match mode {
+        ChromaMode::Independent => (chromas + 1) as f64,
+        ChromaMode::SecondStage => 1.0,
+    }
+}
+
+// None keeps the skin itself
+pub fn pick_chroma<'a, R: Rng + ?Sized>(
+    chromas: &[&'a Chroma],
+    mode: ChromaMode,
+    chroma_chance: f64,
+    rng: &mut R,
+) -> Option<&'a Chroma> {
+    match mode {
+        ChromaMode::Independent => {
+            let i = rng.random_range(0..=chromas.len());
+            i.checked_sub(1).map(|i| chromas[i])
+        }
+        ChromaMode::SecondStage => {
+            if !rng.random_bool(chroma_chance.clamp(0.0, 1.0)) {
+                return None;
+            }
+            chromas.choose(rng).copied()
+        }
+    }
+}
+
+// Chroma colors are given as #rrggbb
+pub fn parse_color(color: &str) -> Option<u32> {
+    u32::from_str_radix(color.trim_start_matches('#'), 16).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::time::Duration;
+
+    fn skin(id: i64, rarity: &str) -> ChampionsCollectionsChampionSkin {
+        ChampionsCollectionsChampionSkin {
+            id,
+            is_base: rarity == "base",
+            rarity_gem_path: format!("/rarity-gem-icons/{}.png", rarity),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn derives_rarity_from_gem_and_type() {
+        assert_eq!(Rarity::of(&skin(1, "base")), Rarity::Base);
+        assert_eq!(Rarity::of(&skin(2, "legendary")), Rarity::Legendary);
+        assert_eq!(Rarity::of(&skin(3, "")), Rarity::Standard);
+        let ultimate = ChampionsCollectionsChampionSkin {
+            skin_type: "Ultimate".to_string(),
+            ..skin(4, "")
+        };
+        assert_eq!(Rarity::of(&ultimate), Rarity::Ultimate);
+    }
+
+    #[test]
+    fn picks_follow_the_rarity_weights() {
+        let weights = RarityWeights(HashMap::from([
+            (Rarity::Base, 1.0),
+            (Rarity::Epic, 2.0),
+            (Rarity::Ultimate, 5.0),
+        ]));
+        let skins = [skin(1, "base"), skin(2, "epic"), skin(3, "ultimate")];
+        let candidates: Vec<&ChampionsCollectionsChampionSkin> =
+            skins.iter().collect();
+        let weight =
+            |skin: &ChampionsCollectionsChampionSkin| weights.skin_weight(skin);
+
+        const DRAWS: usize = 40_000;
+        let mut rng = seeded_rng(7);
+        let mut counts = HashMap::new();
+        for _ in 0..DRAWS {
+            let picked = pick_skin(&candidates, &weight, &mut rng).unwrap();
+            *counts.entry(picked.id).or_insert(0) += 1;
+        }
+
+        let total: f64 = [1.0, 2.0, 5.0].iter().sum();
+        for (id, expected) in [(1, 1.0), (2, 2.0), (3, 5.0)] {
+            let share = counts[&id] as f64 / DRAWS as f64;
+            assert!(
+                (share - expected / total).abs() < 0.01,
+                "skin {} drawn {:.3} of the time",
+                id,
+                share
+            );
+        }
+    }
+
+    #[test]
+    fn zero_weights_pick_nothing() {
+        let weights = RarityWeights(HashMap::from([(Rarity::Epic, 0.0)]));
+        let skins = [skin(1, "epic"), skin(2, "epic")];
+        let candidates: Vec<&ChampionsCollectionsChampionSkin> =
+            skins.iter().collect();
+        let weight =
+            |skin: &ChampionsCollectionsChampionSkin| weights.skin_weight(skin);
+        assert_eq!(pick_skin(&candidates, &weight, &mut seeded_rng(7)), None);
+    }
+
+    #[test]
+    fn seeded_rng_is_stable() {
+        // Pinned, a different value means recorded seeds pick differently
+        let mut rng = seeded_rng(42);
+        assert_eq!(rng.next_u64(), 12578764544318200737);
+    }
+
+    #[test]
+    fn same_seed_same_picks() {
+        let skins: Vec<ChampionsCollectionsChampionSkin> =
+            (1..=20).map(|id| skin(id, "epic")).collect();
+        let candidates: Vec<&ChampionsCollectionsChampionSkin> =
+            skins.iter().collect();
+        let chromas: Vec<Chroma> = (1..=5)
+            .map(|id| Chroma {
+                id,
+                ..Default::default()
+            })
+            .collect();
+        let chromas: Vec<&Chroma> = chromas.iter().collect();
+        let picks = |seed| {
+            let mut rng = seeded_rng(seed);
+            (0..10)
+                .map(|_| {
+                    let skin = pick_skin(&candidates, &|_| 1.0, &mut rng);
+                    let chroma = pick_chroma(
+                        &chromas,
+                        ChromaMode::SecondStage,
+                        0.5,
+                        &mut rng,
+                    );
+                    (skin.unwrap().id, chroma.map(|chroma| chroma.id))
+                })
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(picks(1234), picks(1234));
+        assert_ne!(picks(1234), picks(4321));
+    }
+
+    fn chromas(ids: &[i64]) -> Vec<Chroma> {
+        ids.iter()
+            .map(|id| Chroma {
+                id: *id,
+                ..Default::default()
+            })
+            .collect()
+    }
+
+    // Share of draws each outcome came up, None being the plain skin
+    fn chroma_shares(
+        chromas: &[&Chroma],
+        mode: ChromaMode,
+        chroma_chance: f64,
+    ) -> HashMap<Option<i64>, f64> {
+        const DRAWS: usize = 40_000;
+        let mut rng = seeded_rng(7);
+        let mut counts = HashMap::new();
+        for _ in 0..DRAWS {
+            let picked = pick_chroma(chromas, mode, chroma_chance, &mut rng);
+            *counts.entry(picked.map(|chroma| chroma.id)).or_insert(0) += 1;
+        }
+        counts
+            .into_iter()
+            .map(|(id, count)| (id, count as f64 / DRAWS as f64))
+            .collect()
+    }
+
+    fn assert_share(
+        shares: &HashMap<Option<i64>, f64>,
+        id: Option<i64>,
+        expected: f64,
+    ) {
+        let share = shares.get(&id).copied().unwrap_or_default();
+        assert!(
+            (share - expected).abs() < 0.01,
+            "{:?} drawn {:.3} of the time, expected {:.3}",
+            id,
+            share,
+            expected
+        );
+    }
+
+    #[test]
+    fn roll_weight_counts_independent_chromas() {
+        assert_eq!(roll_weight(ChromaMode::Independent, 0), 1.0);
+        assert_eq!(roll_weight(ChromaMode::Independent, 3), 4.0);
+        assert_eq!(roll_weight(ChromaMode::SecondStage, 0), 1.0);
+        assert_eq!(roll_weight(ChromaMode::SecondStage, 3), 1.0);
+    }
+
+    #[test]
+    fn independent_chromas_are_on_par_with_the_skin() {
+        let chromas = chromas(&[10, 11, 12]);
+        let chromas: Vec<&Chroma> = chromas.iter().collect();
+        // chroma_chance only applies to the second stage
+        let shares = chroma_shares(&chromas, ChromaMode::Independent, 0.0);
+        for id in [None, Some(10), Some(11), Some(12)] {
+            assert_share(&shares, id, 0.25);
+        }
+    }
+
+    #[test]
+    fn second_stage_follows_the_chroma_chance() {
+        let chromas = chromas(&[10, 11]);
+        let chromas: Vec<&Chroma> = chromas.iter().collect();
+        let shares = chroma_shares(&chromas, ChromaMode::SecondStage, 0.3);
+        assert_share(&shares, None, 0.7);
+        assert_share(&shares, Some(10), 0.15);
+        assert_share(&shares, Some(11), 0.15);
+
+        let shares = chroma_shares(&chromas, ChromaMode::SecondStage, 0.0);
+        assert_share(&shares, None, 1.0);
+        let shares = chroma_shares(&chromas, ChromaMode::SecondStage, 1.0);
+        assert_share(&shares, None, 0.0);
+        // Nothing to pick keeps the skin whatever the chance
+        let shares = chroma_shares(&[], ChromaMode::SecondStage, 1.0);
+        assert_share(&shares, None, 1.0);
+    }
+
+    #[test]
+    fn independent_rolls_are_uniform_over_skins_and_chromas() {
+        // A skin without chromas and one with two, four outcomes in all
+        let skins = [skin(1, "epic"), skin(2, "epic")];
+        let candidates: Vec<&ChampionsCollectionsChampionSkin> =
+            skins.iter().collect();
+        let chromas = chromas(&[20, 21]);
+        let chromas: Vec<&Chroma> = chromas.iter().collect();
+        let skin_chromas =
+            |skin: &ChampionsCollectionsChampionSkin| match skin.id {
+                2 => chromas.clone(),
+                _ => Vec::new(),
+            };
+        let weight = |skin: &ChampionsCollectionsChampionSkin| {
+            roll_weight(ChromaMode::Independent, skin_chromas(skin).len())
+        };
+
+        const DRAWS: usize = 40_000;
+        let mut rng = seeded_rng(7);
+        let mut counts = HashMap::new();
+        for _ in 0..DRAWS {
+            let skin = pick_skin(&candidates, &weight, &mut rng).unwrap();
+            let chroma = pick_chroma(
+                &skin_chromas(skin),
+                ChromaMode::Independent,
+                0.5,
+                &mut rng,
+            );
+            let selected = chroma.map_or(skin.id, |chroma| chroma.id);
+            *counts.entry(selected).or_insert(0) += 1;
+        }
+        for id in [1, 2, 20, 21] {
+            let share = counts[&id] as f64 / DRAWS as f64;
+            assert!((share - 0.25).abs() < 0.01, "{} drawn {:.3}", id, share);
+        }
+    }
+
+    #[test]
+    fn daily_seed_depends_on_day_and_phrase() {
+        assert_eq!(
+            daily_seed(20000, "friends"),
+            daily_seed(20000, " friends ")
+        );
+        assert_ne!(daily_seed(20000, "friends"), daily_seed(20001, "friends"));
+        assert_ne!(daily_seed(20000, "friends"), daily_seed(20000, "others"));
+        assert_eq!(utc_day(UNIX_EPOCH + Duration::from_secs(SECS_PER_DAY)), 1);
+    }
+}
@@ -0,0 +1,216 @@
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use std::collections::HashMap;
+use std::error::Error as StdError;
+use std::fs;
+use std::path::Path;
+
+use crate::models::ChampionsCollectionsChampionSkin;
+use crate::selection;
+
+// One round over a champion's skins, nothing repeats until it's empty
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ShuffleBag {
+    pub remaining: Vec<i64>,
+    pub drawn: Vec<i64>,
+}
+
+impl ShuffleBag {
+    // New purchases join the current round, expired or refunded skins leave it
+    pub fn reconcile(&mut self, available: &[i64]) {
+        self.remaining.retain(|id| available.contains(id));
+        self.drawn.retain(|id| available.contains(id));
+        for id in available {
+            if !self.remaining.contains(id) && !self.drawn.contains(id) {
+                self.remaining.push(*id);
+            }
+        }
+    }
+
+    fn refill(&mut self) {
+        self.remaining.append(&mut self.drawn);
+    }
+
+    // Picks from the round without drawing, the skin only leaves the bag
+    // once take confirms it was selected
+    pub fn pick<'a, R: Rng + ?Sized>(
+        &mut self,
+        skins: &[&'a ChampionsCollectionsChampionSkin],
+        weight: &impl Fn(&ChampionsCollectionsChampionSkin) -> f64,
+        rng: &mut R,
+    ) -> Option<&'a ChampionsCollectionsChampionSkin> {
+        let ids: Vec<i64> = skins.iter().map(|skin| skin.id).collect();
+        self.reconcile(&ids);
+        if let Some(skin) = pick_from(skins, &self.remaining, weight, rng) {
+            return Some(skin);
+        }
+
+        // An empty round, or one left with only zero weights, starts over.
+        // The last skin of the previous round sits out the first pick so it
+        // doesn't come twice in a row
+        let previous = self.drawn.last().copied();
+        self.refill();
+        let fresh: Vec<i64> = self
+            .remaining
+            .iter()
+            .copied()
+            .filter(|id| Some(*id) != previous)
+            .collect();
+        pick_from(skins, &fresh, weight, rng)
+            .or_else(|| pick_from(skins, &self.remaining, weight, rng))
+    }
+
+    pub fn take(&mut self, id: i64) {
+        if !self.remaining.contains(&id) {
+            self.refill();
+        }
+        self.remaining.retain(|remaining| *remaining != id);
+        self.drawn.push(id);
+    }
+}
+
+fn pick_from<'a, R: Rng + ?Sized>(
+    skins: &[&'a ChampionsCollectionsChampionSkin],
+    ids: &[i64],
+    weight: &impl Fn(&ChampionsCollectionsChampionSkin) -> f64,
+    rng: &mut R,
+) -> Option<&'a ChampionsCollectionsChampionSkin> {
+    let candidates: Vec<&ChampionsCollectionsChampionSkin> = skins
+        .iter()
+        .copied()
+        .filter(|skin| ids.contains(&skin.id))
+        .collect();
+    selection::pick_skin(&candidates, weight, rng)
+}
+
+// Bags by summoner id, then champion id
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct ShuffleBags(pub HashMap<i64, HashMap<i64, ShuffleBag>>);
+
+impl ShuffleBags {
+    pub fn load(path: &Path) -> Self {
+        let Ok(bags_str) = fs::read_to_string(path) else {
+            return Self::default();
+        };
+        serde_json::from_str(&bags_str).unwrap_or_else(|e| {
+            dbg!(e);
+            Self::default()
+        })
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), Box<dyn StdError>> {
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    pub fn bag(
+        &mut self,
+        summoner_id: i64,
+        champion_id: i64,
+    ) -> &mut ShuffleBag {
+        self.0
+            .entry(summoner_id)
+            .or_default()
+            .entry(champion_id)
+            .or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn skins(ids: &[i64]) -> Vec<ChampionsCollectionsChampionSkin> {
+        ids.iter()
+            .map(|id| ChampionsCollectionsChampionSkin {
+                id: *id,
+                ..Default::default()
+            })
+            .collect()
+    }
+
+    fn uniform(_: &ChampionsCollectionsChampionSkin) -> f64 {
+        1.0
+    }
+
+    // Picks and takes, like a selection that went through
+    fn draw(
+        bag: &mut ShuffleBag,
+        skins: &[ChampionsCollectionsChampionSkin],
+        rng: &mut impl Rng,
+    ) -> i64 {
+        let skins: Vec<&ChampionsCollectionsChampionSkin> =
+            skins.iter().collect();
+        let id = bag.pick(&skins, &uniform, rng).unwrap().id;
+        bag.take(id);
+        id
+    }
+
+    #[test]
+    fn reconcile_adds_purchases_and_drops_expired() {
+        let mut bag = ShuffleBag {
+            remaining: vec![1, 2],
+            drawn: vec![3, 4],
+        };
+        bag.reconcile(&[2, 3, 5]);
+        assert_eq!(
+            bag,
+            ShuffleBag {
+                remaining: vec![2, 5],
+                drawn: vec![3],
+            }
+        );
+    }
+
+    #[test]
+    fn every_skin_once_per_round() {
+        let skins = skins(&[1, 2, 3, 4]);
+        let mut bag = ShuffleBag::default();
+        let mut rng = selection::seeded_rng(1);
+        for _ in 0..10 {
+            let mut round: Vec<i64> =
+                (0..4).map(|_| draw(&mut bag, &skins, &mut rng)).collect();
+            round.sort();
+            assert_eq!(round, [1, 2, 3, 4]);
+        }
+    }
+
+    #[test]
+    fn refill_never_repeats_the_last_skin() {
+        let skins = skins(&[1, 2, 3]);
+        for seed in 0..50 {
+            let mut bag = ShuffleBag::default();
+            let mut rng = selection::seeded_rng(seed);
+            let mut previous = draw(&mut bag, &skins, &mut rng);
+            for _ in 0..9 {
+                let id = draw(&mut bag, &skins, &mut rng);
+                assert_ne!(id, previous, "seed {}", seed);
+                previous = id;
+            }
+        }
+    }
+
+    #[test]
+    fn single_skin_repeats() {
+        let skins = skins(&[1]);
+        let mut bag = ShuffleBag::default();
+        let mut rng = selection::seeded_rng(1);
+        assert_eq!(draw(&mut bag, &skins, &mut rng), 1);
+        assert_eq!(draw(&mut bag, &skins, &mut rng), 1);
+    }
+
+    #[test]
+    fn pick_leaves_the_skin_in_the_bag() {
+        let skins = skins(&[1, 2]);
+        let candidates: Vec<&ChampionsCollectionsChampionSkin> =
+            skins.iter().collect();
+        let mut bag = ShuffleBag::default();
+        let mut rng = selection::seeded_rng(1);
+        bag.pick(&candidates, &uniform, &mut rng).unwrap();
+        assert_eq!(bag.remaining, [1, 2]);
+        assert!(bag.drawn.is_empty());
+    }
+}
@@ -0,0 +1,145 @@
+mod common;
+
+use lol_skin_randomizer::auto::{AutoMode, AutoRandomizer};
+use lol_skin_randomizer::config::Config;
+use lol_skin_randomizer::discovery::CliSource;
+use lol_skin_randomizer::models::ChampSelectSession;
+use lol_skin_randomizer::registry::ClientRegistry;
+use mock_lcu::{MockLcu, MockResponse, MockState, TimelineStep};
+use tempfile::TempDir;
+
+const MY_SELECTION: &str = "/lol-champ-select/v1/session/my-selection";
+
+// Plays a timeline step by step, handing every session to the randomizer
+// and randomizing whenever it's due, like the event thread does
+struct Harness {
+    mock: MockLcu,
+    registry: ClientRegistry,
+    auto: AutoRandomizer,
+    port: String,
+    // The skin each fired randomization selected, None when it failed
+    fired: Vec<Option<i64>>,
+    // Persisted state lives here until the harness is dropped
+    _dir: TempDir,
+}
+
+impl Harness {
+    fn start(state: MockState, name: &str) -> Self {
+        let mock = MockLcu::start(common::TOKEN, state).unwrap();
+        let dir = common::temp_dir(name);
+        let config = Config {
+            auto_randomize: AutoMode::Skin,
+            ..common::config(&dir)
+        };
+        let source = CliSource {
+            port: Some(mock.port.to_string()),
+            auth_token: Some(common::TOKEN.to_string()),
+        };
+        let mut registry =
+            ClientRegistry::with_sources(&config, vec![Box::new(source)]);
+        registry.refresh().unwrap();
+        Self {
+            port: mock.port.to_string(),
+            mock,
+            registry,
+            auto: AutoRandomizer::new(&config),
+            fired: Vec::new(),
+            _dir: dir,
+        }
+    }
+
+    fn session(&self) -> Option<ChampSelectSession> {
+        let session = self.mock.state.lock().unwrap().session.clone()?;
+        Some(serde_json::from_value(session).unwrap())
+    }
+
+    // A selection comes back as another session update, which is delivered
+    // too before the next step
+    fn deliver(&mut self) {
+        loop {
+            let session = self.session();
+            let Some(due) =
+                self.auto.on_session(&self.port, session.as_ref(), 0)
+            else {
+                return;
+            };
+            let due = due.unwrap();
+            assert_eq!(due.port, self.port);
+            let outcomes =
+                self.registry.auto_randomize(&due.port, due.mode).unwrap();
+            let selected = match outcomes.as_slice() {
+                [(_, Ok(outcome))] => Some(outcome.selected_id()),
+                _ => None,
+            };
+            self.auto.on_randomized(&due.port, selected);
+            self.fired.push(selected);
+        }
+    }
+
+    fn play(&mut self, timeline: &[TimelineStep]) {
+        for step in timeline {
+            self.mock.apply(step);
+            self.deliver();
+        }
+    }
+}
+
+#[test]
+fn randomizes_once_the_pick_is_locked() {
+    let mut harness = Harness::start(common::champ_select(), "auto-locked");
+    harness.play(&common::timeline("auto_timeline.json", 0));
+
+    assert_eq!(harness.fired.len(), 1);
+    let selected = harness.fired[0].unwrap();
+    assert_eq!(selected / 1000, 103);
+    assert_eq!(common::selected_skin_ids(&harness.mock), [selected]);
+}
+
+// The swap timeline changes the skin by hand before the reroll, so Ahri
+// isn't randomized again when traded back
+fn swap_timeline() -> Vec<TimelineStep> {
+    let mut timeline = common::timeline("swap_timeline.json", 0);
+    // Keeps the first selection from landing on the one picked by hand
+    timeline[0].pickable_skin_ids = Some(vec![103000, 103002, 103003]);
+    timeline
+}
+
+#[test]
+fn randomizes_each_new_champion_but_not_a_manual_one() {
+    let mut harness = Harness::start(common::champ_select(), "auto-swap");
+    harness.play(&swap_timeline());
+
+    assert_eq!(harness.fired.len(), 2);
+    let [ahri, nunu] = [harness.fired[0].unwrap(), harness.fired[1].unwrap()];
+    assert_eq!((ahri / 1000, nunu / 1000), (103, 22));
+    assert_eq!(common::selected_skin_ids(&harness.mock), [ahri, nunu]);
+}
+
+#[test]
+fn failed_randomization_leaves_manual_changes_manual() {
+    let mut state = common::champ_select();
+    state.responses.insert(
+        MY_SELECTION.to_string(),
+        MockResponse {
+            status: 500,
+            body: "busy".to_string(),
+        },
+    );
+    let mut harness = Harness::start(state, "auto-failed");
+    harness.play(&swap_timeline());
+
+    // Without clearing pending the hand picked skin would pass for the
+    // failed selection, and Ahri be randomized again after the trade
+    assert_eq!(harness.fired, [None, None]);
+}
+
+#[test]
+fn unchanged_skin_leaves_manual_changes_manual() {
+    let mut harness = Harness::start(common::champ_select(), "auto-same");
+    let mut timeline = swap_timeline();
+    timeline[0].pickable_skin_ids = Some(vec![103000]);
+    harness.play(&timeline);
+
+    assert_eq!(harness.fired.len(), 2);
+    assert_eq!(harness.fired[0], Some(103000));
+}
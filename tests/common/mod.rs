@@ -0,0 +1,74 @@
+// Shared by the integration tests, not every test file uses every helper
+#![allow(dead_code)]
+
+use lol_skin_randomizer::config::Config;
+use lol_skin_randomizer::discovery::PortAndToken;
+use lol_skin_randomizer::lcu::GameClient;
+use mock_lcu::{MockLcu, MockState, TimelineStep};
+use tempfile::TempDir;
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+pub const TOKEN: &str = "test-token";
+
+fn fixture_path(name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("mock-lcu")
+        .join("fixtures")
+        .join(name)
+}
+
+// Ahri locked in with four pickable skins, one of them with chromas
+pub fn champ_select() -> MockState {
+    let state = fs::read_to_string(fixture_path("champ_select.json")).unwrap();
+    serde_json::from_str(&state).unwrap()
+}
+
+// Delays are cut down so the tests don't sit through a whole draft
+pub fn timeline(name: &str, delay_ms: u64) -> Vec<TimelineStep> {
+    let timeline = fs::read_to_string(fixture_path(name)).unwrap();
+    let mut timeline: Vec<TimelineStep> =
+        serde_json::from_str(&timeline).unwrap();
+    for step in &mut timeline {
+        step.delay_ms = delay_ms;
+    }
+    timeline
+}
+
+// A fresh directory per test, deleted once the test drops it
+pub fn temp_dir(name: &str) -> TempDir {
+    tempfile::Builder::new().prefix(name).tempdir().unwrap()
+}
+
+// The mock's certificate is self signed, persisted state stays in the test's
+// own directory
+pub fn config(dir: &TempDir) -> Config {
+    Config {
+        insecure_tls: true,
+        data_dir: Some(dir.path().to_path_buf()),
+        ..Default::default()
+    }
+}
+
+pub fn credentials(mock: &MockLcu) -> PortAndToken {
+    PortAndToken {
+        port: mock.port.to_string(),
+        auth_token: mock.token.clone(),
+        riot_client: None,
+    }
+}
+
+pub fn connect(mock: &MockLcu, config: &Config) -> GameClient {
+    let mut client = GameClient::new(config);
+    client.connect("test", credentials(mock)).unwrap();
+    client
+}
+
+// Skin ids of the captured my-selection bodies, in order
+pub fn selected_skin_ids(mock: &MockLcu) -> Vec<i64> {
+    mock.selections()
+        .iter()
+        .filter_map(|selection| selection["selectedSkinId"].as_i64())
+        .collect()
+}
@@ -0,0 +1,157 @@
+mod common;
+
+use lol_skin_randomizer::connection::{self, Backoff, ConnectionState};
+use lol_skin_randomizer::discovery::{CredentialSource, PortAndToken};
+use lol_skin_randomizer::lcu::ClientHealth;
+use lol_skin_randomizer::registry::ClientRegistry;
+use mock_lcu::MockLcu;
+use tempfile::TempDir;
+
+use std::error::Error as StdError;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Receiver};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+// Stands in for the lockfile or process scan, the test starts and stops the
+// client it finds
+#[derive(Debug, Clone, Default)]
+struct ScriptedSource {
+    credentials: Arc<Mutex<Option<PortAndToken>>>,
+    discoveries: Arc<AtomicUsize>,
+}
+
+impl ScriptedSource {
+    fn set(&self, credentials: Option<PortAndToken>) {
+        *self.credentials.lock().unwrap() = credentials;
+    }
+}
+
+impl CredentialSource for ScriptedSource {
+    fn name(&self) -> &'static str {
+        "scripted"
+    }
+
+    fn discover(&self) -> Result<PortAndToken, Box<dyn StdError>> {
+        self.discoveries.fetch_add(1, Ordering::SeqCst);
+        self.credentials
+            .lock()
+            .unwrap()
+            .clone()
+            .ok_or_else(|| "No client running".into())
+    }
+}
+
+fn registry(source: &ScriptedSource, dir: &TempDir) -> ClientRegistry {
+    ClientRegistry::with_sources(
+        &common::config(dir),
+        vec![Box::new(source.clone())],
+    )
+}
+
+// Runs the connection in the background for the rest of the test
+fn spawn_connection(
+    registry: ClientRegistry,
+) -> (Receiver<ConnectionState>, Receiver<Vec<String>>) {
+    let (states, state_rx) = mpsc::channel();
+    let (ports, ports_rx) = mpsc::channel();
+    let registry = Mutex::new(registry);
+    thread::spawn(move || {
+        connection::run(
+            &registry,
+            Backoff::new(Duration::from_millis(10), Duration::from_millis(40)),
+            |state| {
+                let _ = states.send(state);
+            },
+            |list| {
+                let _ = ports.send(list.into_iter().map(|c| c.port).collect());
+            },
+            |_, _| {},
+        )
+    });
+    (state_rx, ports_rx)
+}
+
+fn wait_for<T: std::fmt::Debug>(
+    rx: &Receiver<T>,
+    matches: impl Fn(&T) -> bool,
+) -> T {
+    let deadline = Instant::now() + Duration::from_secs(10);
+    loop {
+        let left = deadline.saturating_duration_since(Instant::now());
+        match rx.recv_timeout(left) {
+            Ok(value) if matches(&value) => return value,
+            Ok(_) => {}
+            Err(e) => panic!("Gave up waiting: {}", e),
+        }
+    }
+}
+
+#[test]
+fn follows_client_appearing_disappearing_and_restarting() {
+    let source = ScriptedSource::default();
+    let dir = common::temp_dir("appear");
+    let (states, ports) = spawn_connection(registry(&source, &dir));
+
+    // Nothing running yet
+    wait_for(&states, |s| matches!(s, ConnectionState::Failed(_)));
+
+    let mock = MockLcu::start(common::TOKEN, common::champ_select()).unwrap();
+    source.set(Some(common::credentials(&mock)));
+    let connected =
+        wait_for(&states, |s| matches!(s, ConnectionState::Connected(_)));
+    assert_eq!(
+        connected,
+        ConnectionState::Connected(ClientHealth::ChampSelect)
+    );
+    let port = mock.port.to_string();
+    wait_for(&ports, |list| *list == [port.as_str()]);
+
+    // Closing the client closes its event socket
+    source.set(None);
+    drop(mock);
+    wait_for(&states, |s| *s == ConnectionState::Disconnected);
+    wait_for(&states, |s| matches!(s, ConnectionState::Failed(_)));
+    wait_for(&ports, |list| list.is_empty());
+
+    // Back on another port with another token
+    let restarted =
+        MockLcu::start("new-token", common::champ_select()).unwrap();
+    source.set(Some(common::credentials(&restarted)));
+    wait_for(&states, |s| matches!(s, ConnectionState::Connected(_)));
+    let port = restarted.port.to_string();
+    wait_for(&ports, |list| *list == [port.as_str()]);
+}
+
+#[test]
+fn periodic_refresh_leaves_discovery_to_the_backoff() {
+    let source = ScriptedSource::default();
+    let dir = common::temp_dir("periodic-refresh");
+    let registry = Mutex::new(registry(&source, &dir));
+
+    ClientRegistry::refresh_shared_if_connected(&registry).unwrap();
+    assert_eq!(source.discoveries.load(Ordering::SeqCst), 0);
+
+    let mock = MockLcu::start(common::TOKEN, common::champ_select()).unwrap();
+    source.set(Some(common::credentials(&mock)));
+    ClientRegistry::refresh_shared(&registry).unwrap();
+    ClientRegistry::refresh_shared_if_connected(&registry).unwrap();
+    assert_eq!(source.discoveries.load(Ordering::SeqCst), 2);
+    assert_eq!(registry.lock().unwrap().list().len(), 1);
+}
+
+#[test]
+fn backoff_doubles_up_to_the_cap() {
+    let source = ScriptedSource::default();
+    let dir = common::temp_dir("backoff");
+    let (states, _ports) = spawn_connection(registry(&source, &dir));
+
+    // 10, 20, 40, 40ms between attempts, so five failures take at least 110ms
+    let started = Instant::now();
+    for _ in 0..5 {
+        wait_for(&states, |s| matches!(s, ConnectionState::Failed(_)));
+    }
+    assert!(started.elapsed() >= Duration::from_millis(110));
+    assert!(source.discoveries.load(Ordering::SeqCst) <= 6);
+}
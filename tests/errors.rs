@@ -0,0 +1,249 @@
+mod common;
+
+use lol_skin_randomizer::endpoints;
+use lol_skin_randomizer::error::LcuError;
+use lol_skin_randomizer::lcu::GameClient;
+use lol_skin_randomizer::selection::{Rarity, RarityWeights};
+use mock_lcu::{MockLcu, MockResponse};
+use serde_json::json;
+
+use std::fs;
+
+#[test]
+fn wrong_token_is_unauthorized() {
+    let mock = MockLcu::start(common::TOKEN, common::champ_select()).unwrap();
+    let mut credentials = common::credentials(&mock);
+    credentials.auth_token = "wrong-token".to_string();
+    let dir = common::temp_dir("401");
+    let mut client = GameClient::new(&common::config(&dir));
+    client.connect("test", credentials).unwrap();
+
+    assert!(matches!(client.health(), Err(LcuError::Unauthorized)));
+    assert!(matches!(client.set_skin(), Err(LcuError::Unauthorized)));
+}
+
+#[test]
+fn closed_port_is_client_not_running() {
+    let mock = MockLcu::start(common::TOKEN, common::champ_select()).unwrap();
+    let dir = common::temp_dir("not-running");
+    let client = common::connect(&mock, &common::config(&dir));
+    drop(mock);
+
+    assert!(matches!(client.set_skin(), Err(LcuError::ClientNotRunning)));
+    assert!(matches!(
+        client.set_chroma(),
+        Err(LcuError::ClientNotRunning)
+    ));
+}
+
+#[test]
+fn missing_session_is_not_in_champ_select() {
+    let mut state = common::champ_select();
+    state.session = None;
+    let mock = MockLcu::start(common::TOKEN, state).unwrap();
+    let dir = common::temp_dir("404-session");
+    let client = common::connect(&mock, &common::config(&dir));
+
+    assert!(matches!(client.set_skin(), Err(LcuError::NotInChampSelect)));
+    assert!(matches!(
+        client.set_chroma(),
+        Err(LcuError::NotInChampSelect)
+    ));
+    assert!(mock.selections().is_empty());
+}
+
+#[test]
+fn zero_rarity_weights_are_reported() {
+    let mock = MockLcu::start(common::TOKEN, common::champ_select()).unwrap();
+    let dir = common::temp_dir("zero-weights");
+    let mut config = common::config(&dir);
+    config.rarity_weights = RarityWeights(
+        [
+            Rarity::Base,
+            Rarity::Standard,
+            Rarity::Epic,
+            Rarity::Legendary,
+            Rarity::Mythic,
+            Rarity::Ultimate,
+            Rarity::Transcendent,
+            Rarity::Exalted,
+        ]
+        .into_iter()
+        .map(|rarity| (rarity, 0.0))
+        .collect(),
+    );
+    let client = common::connect(&mock, &config);
+
+    assert!(matches!(
+        client.set_skin(),
+        Err(LcuError::ZeroWeights("skins"))
+    ));
+    assert!(mock.selections().is_empty());
+}
+
+#[test]
+fn malformed_body_is_decode_error() {
+    let mut state = common::champ_select();
+    state.responses.insert(
+        "/lol-summoner/v1/current-summoner".to_string(),
+        MockResponse {
+            status: 200,
+            body: r#"{"gameName": 42"#.to_string(),
+        },
+    );
+    let mock = MockLcu::start(common::TOKEN, state).unwrap();
+    let dir = common::temp_dir("bad-json");
+    let client = common::connect(&mock, &common::config(&dir));
+
+    let err = client.summoner_name().unwrap_err();
+    assert!(
+        matches!(&err, LcuError::Decode { endpoint, .. }
+            if endpoint == "lol-summoner/v1/current-summoner"),
+        "{:?}",
+        err
+    );
+}
+
+#[test]
+fn lcu_error_body_is_api_error() {
+    let mock = MockLcu::start(common::TOKEN, common::champ_select()).unwrap();
+    let dir = common::temp_dir("api-error");
+    let client = common::connect(&mock, &common::config(&dir));
+
+    // The fixture only has inventories for Ahri and Ashe
+    let err = client
+        .request(&endpoints::ChampionSkins {
+            summoner_id: 1001,
+            champion_id: 23,
+        })
+        .unwrap_err();
+    let LcuError::Api { code, error, .. } = err else {
+        panic!("Expected an api error, got {:?}", err);
+    };
+    assert_eq!(code, 404);
+    assert_eq!(error.error_code, "RESOURCE_NOT_FOUND");
+}
+
+#[test]
+fn other_error_body_is_http_status() {
+    let mut state = common::champ_select();
+    state.responses.insert(
+        "/lol-gameflow/v1/gameflow-phase".to_string(),
+        MockResponse {
+            status: 500,
+            body: "Internal Server Error".to_string(),
+        },
+    );
+    let mock = MockLcu::start(common::TOKEN, state).unwrap();
+    let dir = common::temp_dir("http-status");
+    let client = common::connect(&mock, &common::config(&dir));
+
+    let err = client.health().unwrap_err();
+    assert!(
+        matches!(&err, LcuError::HttpStatus { code: 500, body }
+            if body == "Internal Server Error"),
+        "{:?}",
+        err
+    );
+}
+
+#[test]
+fn no_champion_is_champion_not_picked() {
+    let mut state = common::champ_select();
+    state.current_champion = 0;
+    state.session.as_mut().unwrap()["myTeam"][0]["championId"] = json!(0);
+    let mock = MockLcu::start(common::TOKEN, state).unwrap();
+    let dir = common::temp_dir("not-picked");
+    let client = common::connect(&mock, &common::config(&dir));
+
+    assert!(matches!(
+        client.set_skin(),
+        Err(LcuError::ChampionNotPicked)
+    ));
+    assert!(matches!(
+        client.set_chroma(),
+        Err(LcuError::ChampionNotPicked)
+    ));
+    assert!(mock.selections().is_empty());
+}
+
+#[test]
+fn nothing_owned_is_no_candidates() {
+    let mut state = common::champ_select();
+    state.pickable_skin_ids.clear();
+    let mock = MockLcu::start(common::TOKEN, state).unwrap();
+    let dir = common::temp_dir("no-candidates");
+    let client = common::connect(&mock, &common::config(&dir));
+
+    assert!(matches!(
+        client.set_skin(),
+        Err(LcuError::NoCandidates("skins"))
+    ));
+    // The selected base skin has no chromas at all
+    assert!(matches!(
+        client.set_chroma(),
+        Err(LcuError::NoCandidates("chroma"))
+    ));
+    assert!(mock.selections().is_empty());
+}
+
+#[test]
+fn ranked_queue_is_blocked() {
+    let mut state = common::champ_select();
+    state.session.as_mut().unwrap()["queueId"] = json!(420);
+    let mock = MockLcu::start(common::TOKEN, state).unwrap();
+    let dir = common::temp_dir("blocked");
+    let client = common::connect(&mock, &common::config(&dir));
+
+    let err = client.set_skin().unwrap_err();
+    assert!(
+        matches!(&err, LcuError::Blocked(reason)
+            if reason == "queue 420 is not allowed"),
+        "{:?}",
+        err
+    );
+    assert!(mock.selections().is_empty());
+}
+
+#[test]
+fn filters_leaving_nothing_are_filtered_out() {
+    let mut state = common::champ_select();
+    // Midnight Ahri, whose only owned chroma is blacklisted too
+    state.session.as_mut().unwrap()["myTeam"][0]["selectedSkinId"] =
+        json!(103002);
+    let mock = MockLcu::start(common::TOKEN, state).unwrap();
+    let dir = common::temp_dir("filtered-out");
+    let config = common::config(&dir);
+    fs::write(
+        config.skin_filters_path(),
+        r#"{"103": {"blacklist": [103000, 103001, 103002, 103003, 103020]}}"#,
+    )
+    .unwrap();
+    let client = common::connect(&mock, &config);
+
+    assert!(matches!(
+        client.set_skin(),
+        Err(LcuError::FilteredOut("skins"))
+    ));
+    assert!(matches!(
+        client.set_chroma(),
+        Err(LcuError::FilteredOut("chroma"))
+    ));
+    assert!(mock.selections().is_empty());
+}
+
+#[test]
+fn malformed_filters_are_reported() {
+    let mock = MockLcu::start(common::TOKEN, common::champ_select()).unwrap();
+    let dir = common::temp_dir("bad-filters");
+    let config = common::config(&dir);
+    fs::write(config.skin_filters_path(), r#"{"103": {"blacklist": ["#)
+        .unwrap();
+    let client = common::connect(&mock, &config);
+
+    assert!(matches!(
+        client.set_skin(),
+        Err(LcuError::InvalidFilters(_))
+    ));
+    assert!(mock.selections().is_empty());
+}
@@ -0,0 +1,102 @@
+mod common;
+
+use lol_skin_randomizer::fixtures::{Fixture, Recorder};
+use lol_skin_randomizer::lcu::GameClient;
+use lol_skin_randomizer::selection::{Rarity, SkinMode};
+use mock_lcu::MockLcu;
+
+use std::fs;
+use std::sync::Arc;
+
+#[test]
+fn replay_picks_the_recorded_skins() {
+    let mock = MockLcu::start(common::TOKEN, common::champ_select()).unwrap();
+    let dir = common::temp_dir("record-replay");
+    let mut config = common::config(&dir);
+    config.skin_mode = SkinMode::Random;
+    config.rarity_weights.0.insert(Rarity::Epic, 4.0);
+    let path = config.data_dir.clone().unwrap().join("fixture.json");
+    let mut client = common::connect(&mock, &config);
+    client.set_recorder(Arc::new(Recorder::new(&path, &config)));
+
+    let recorded: Vec<_> = (0..3).map(|_| client.set_skin().unwrap()).collect();
+
+    assert!(!fs::read_to_string(&path).unwrap().contains(common::TOKEN));
+    let fixture = Fixture::load(&path).unwrap();
+    let seeds: Vec<u64> = recorded.iter().map(|outcome| outcome.seed).collect();
+    assert_eq!(fixture.seeds, seeds);
+    assert_eq!(fixture.config.as_ref(), Some(&config));
+
+    // No client is running any more, everything comes from the fixture
+    drop(mock);
+    let replay = GameClient::replay(fixture);
+    let replayed: Vec<_> = (0..3).map(|_| replay.set_skin().unwrap()).collect();
+    assert_eq!(replayed, recorded);
+}
+
+#[test]
+fn clients_share_one_recording() {
+    let first = MockLcu::start(common::TOKEN, common::champ_select()).unwrap();
+    let second = MockLcu::start(common::TOKEN, common::champ_select()).unwrap();
+    let dir = common::temp_dir("shared-recorder");
+    let config = common::config(&dir);
+    let path = config.data_dir.clone().unwrap().join("fixture.json");
+    let recorder = Arc::new(Recorder::new(&path, &config));
+    let mut first_client = common::connect(&first, &config);
+    let mut second_client = common::connect(&second, &config);
+    first_client.set_recorder(recorder.clone());
+    second_client.set_recorder(recorder);
+
+    first_client.health().unwrap();
+    second_client.summoner_name().unwrap();
+    first_client.health().unwrap();
+
+    let paths: Vec<String> = Fixture::load(&path)
+        .unwrap()
+        .exchanges
+        .into_iter()
+        .map(|exchange| exchange.path)
+        .collect();
+    assert_eq!(
+        paths,
+        [
+            "lol-gameflow/v1/gameflow-phase",
+            "lol-summoner/v1/current-summoner",
+            "lol-gameflow/v1/gameflow-phase",
+        ]
+    );
+}
+
+#[test]
+fn replay_ignores_the_local_state() {
+    let mock = MockLcu::start(common::TOKEN, common::champ_select()).unwrap();
+    let dir = common::temp_dir("replay-state");
+    let mut config = common::config(&dir);
+    config.skin_mode = SkinMode::ShuffleBag;
+    let data_dir = config.data_dir.clone().unwrap();
+    let path = data_dir.join("fixture.json");
+    // Dynasty Ahri is blacklisted while recording
+    fs::write(
+        config.skin_filters_path(),
+        r#"{"103": {"blacklist": [103001]}}"#,
+    )
+    .unwrap();
+    let mut client = common::connect(&mock, &config);
+    client.set_recorder(Arc::new(Recorder::new(&path, &config)));
+
+    let recorded: Vec<_> = (0..3).map(|_| client.set_skin().unwrap()).collect();
+    assert!(recorded.iter().all(|outcome| outcome.skin_id != 103001));
+
+    // A different machine, with other filters and no shuffle bags
+    drop(mock);
+    fs::write(
+        config.skin_filters_path(),
+        r#"{"103": {"favorites": [103001]}}"#,
+    )
+    .unwrap();
+    fs::remove_file(config.shuffle_bags_path()).unwrap();
+    let replay = GameClient::replay(Fixture::load(&path).unwrap());
+    let replayed: Vec<_> = (0..3).map(|_| replay.set_skin().unwrap()).collect();
+    assert_eq!(replayed, recorded);
+    assert!(!config.shuffle_bags_path().exists());
+}
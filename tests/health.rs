@@ -0,0 +1,81 @@
+mod common;
+
+use lol_skin_randomizer::config::Config;
+use lol_skin_randomizer::error::LcuError;
+use lol_skin_randomizer::lcu::ClientHealth;
+use mock_lcu::{MockLcu, MockResponse};
+
+use std::time::{Duration, Instant};
+
+#[test]
+fn stalled_response_times_out() {
+    let mut state = common::champ_select();
+    state.stall_ms = 1000;
+    let mock = MockLcu::start(common::TOKEN, state).unwrap();
+    let dir = common::temp_dir("timeout");
+    let config = Config {
+        request_timeout_ms: 200,
+        ..common::config(&dir)
+    };
+    let client = common::connect(&mock, &config);
+
+    let started = Instant::now();
+    let err = client.health().unwrap_err();
+    assert!(
+        matches!(&err, LcuError::Request(e) if e.is_timeout()),
+        "{:?}",
+        err
+    );
+    assert!(started.elapsed() < Duration::from_millis(800));
+
+    // Not mistaken for a client that isn't running
+    let err = client.set_skin().unwrap_err();
+    assert!(
+        matches!(&err, LcuError::Request(e) if e.is_timeout()),
+        "{:?}",
+        err
+    );
+}
+
+#[test]
+fn health_follows_the_gameflow_phase() {
+    let mock = MockLcu::start(common::TOKEN, common::champ_select()).unwrap();
+    let dir = common::temp_dir("health");
+    let client = common::connect(&mock, &common::config(&dir));
+
+    for (phase, health) in [
+        ("None", ClientHealth::Idle),
+        ("Lobby", ClientHealth::Lobby),
+        ("Matchmaking", ClientHealth::Lobby),
+        ("ReadyCheck", ClientHealth::Lobby),
+        ("ChampSelect", ClientHealth::ChampSelect),
+        ("GameStart", ClientHealth::InGame),
+        ("InProgress", ClientHealth::InGame),
+        ("Reconnect", ClientHealth::InGame),
+        ("EndOfGame", ClientHealth::Other("EndOfGame".to_string())),
+    ] {
+        mock.update(|state| state.gameflow_phase = phase.to_string());
+        assert_eq!(client.health().unwrap(), health, "{}", phase);
+    }
+}
+
+// The gameflow plugin isn't up before an account is logged in
+#[test]
+fn health_without_gameflow_is_not_logged_in() {
+    for status in [404, 503] {
+        let mut state = common::champ_select();
+        state.responses.insert(
+            "/lol-gameflow/v1/gameflow-phase".to_string(),
+            MockResponse {
+                status,
+                body: String::new(),
+            },
+        );
+        let mock = MockLcu::start(common::TOKEN, state).unwrap();
+        let dir = common::temp_dir("not-logged-in");
+        let client = common::connect(&mock, &common::config(&dir));
+
+        assert_eq!(client.health().unwrap(), ClientHealth::NotLoggedIn);
+        assert!(matches!(client.set_skin(), Err(LcuError::NotLoggedIn)));
+    }
+}
@@ -0,0 +1,104 @@
+mod common;
+
+use lol_skin_randomizer::events::{self, LcuEvent};
+use lol_skin_randomizer::history::{PlayHistory, PlayTracker, Played};
+use lol_skin_randomizer::models::ChampSelectSession;
+use mock_lcu::MockLcu;
+use serde_json::json;
+
+use std::sync::mpsc;
+use std::thread;
+
+fn session_event(skin_id: i64) -> LcuEvent {
+    let mut session = common::champ_select().session.unwrap();
+    session["myTeam"][0]["selectedSkinId"] = json!(skin_id);
+    let session: ChampSelectSession = serde_json::from_value(session).unwrap();
+    LcuEvent::ChampSelectSession(Some(Box::new(session)))
+}
+
+fn phase_event(phase: &str) -> LcuEvent {
+    LcuEvent::GameflowPhase(phase.to_string())
+}
+
+#[test]
+fn skin_selected_when_the_game_starts_is_played() {
+    let mock = MockLcu::start(common::TOKEN, common::champ_select()).unwrap();
+    let dir = common::temp_dir("played-at-start");
+    let config = common::config(&dir);
+    let client = common::connect(&mock, &config);
+    let mut socket = client.subscribe_events().unwrap();
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        let _ = events::listen(&mut socket, |e| tx.send(e).unwrap());
+    });
+
+    // A pick alone doesn't count, the game may still be dodged
+    client.set_skin().unwrap();
+    let history = PlayHistory::load(&config.play_history_path());
+    assert_eq!(history, PlayHistory::default());
+
+    mock.play(&common::timeline("swap_timeline.json", 10));
+    let mut plays = PlayTracker::default();
+    let played = rx.iter().find_map(|event| plays.on_event(&event)).unwrap();
+    assert_eq!(
+        played,
+        Played {
+            summoner_id: 1001,
+            champion_id: 103,
+            skin_id: 103001,
+        }
+    );
+
+    client.record_played(&played).unwrap();
+    let history = PlayHistory::load(&config.play_history_path());
+    assert!(history.last_played(1001, 103001).is_some());
+    assert_eq!(history.0[&1001].len(), 1);
+}
+
+#[test]
+fn chroma_counts_for_its_skin() {
+    let mock = MockLcu::start(common::TOKEN, common::champ_select()).unwrap();
+    let dir = common::temp_dir("played-chroma");
+    let config = common::config(&dir);
+    let client = common::connect(&mock, &config);
+
+    client
+        .record_played(&Played {
+            summoner_id: 1001,
+            champion_id: 103,
+            skin_id: 103020,
+        })
+        .unwrap();
+
+    let history = PlayHistory::load(&config.play_history_path());
+    assert!(history.last_played(1001, 103002).is_some());
+    assert!(history.last_played(1001, 103020).is_none());
+}
+
+#[test]
+fn dodged_champ_select_isnt_played() {
+    let mut plays = PlayTracker::default();
+    assert_eq!(plays.on_event(&session_event(103001)), None);
+    assert_eq!(plays.on_event(&phase_event("Lobby")), None);
+    assert_eq!(plays.on_event(&phase_event("InProgress")), None);
+}
+
+#[test]
+fn last_selection_is_played_once() {
+    let mut plays = PlayTracker::default();
+    plays.on_event(&session_event(103001));
+    plays.on_event(&session_event(103002));
+    plays.on_event(&LcuEvent::ChampSelectSession(None));
+    assert_eq!(plays.on_event(&phase_event("GameStart")), None);
+    assert_eq!(
+        plays.on_event(&phase_event("InProgress")),
+        Some(Played {
+            summoner_id: 1001,
+            champion_id: 103,
+            skin_id: 103002,
+        })
+    );
+    // Reconnecting after a crash is the same game
+    plays.on_event(&phase_event("Reconnect"));
+    assert_eq!(plays.on_event(&phase_event("InProgress")), None);
+}
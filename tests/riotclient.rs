@@ -0,0 +1,65 @@
+mod common;
+
+use lol_skin_randomizer::riotclient::{RiotClient, RiotClientStatus};
+use mock_lcu::{MockLcu, MockState};
+use serde_json::json;
+use tempfile::TempDir;
+
+// The mock stands in for the Riot Client, only its product sessions matter
+fn riot_client(mock: &MockLcu, dir: &TempDir) -> RiotClient {
+    RiotClient::connect(
+        &common::config(dir),
+        None,
+        "test",
+        common::credentials(mock),
+    )
+    .unwrap()
+}
+
+#[test]
+fn league_session_is_league_launched() {
+    let state = MockState {
+        product_sessions: json!({
+            "host_app": { "productId": "riot_client", "phase": "Idle" },
+            "league_of_legends": {
+                "productId": "league_of_legends",
+                "patchlineId": "live",
+                "phase": "Gameplay"
+            }
+        }),
+        ..Default::default()
+    };
+    let mock = MockLcu::start(common::TOKEN, state).unwrap();
+    let dir = common::temp_dir("riot-league");
+    let client = riot_client(&mock, &dir);
+
+    let mut products: Vec<String> = client
+        .product_sessions()
+        .unwrap()
+        .into_iter()
+        .map(|session| session.product_id)
+        .collect();
+    products.sort();
+    assert_eq!(products, ["league_of_legends", "riot_client"]);
+    assert_eq!(client.status(), RiotClientStatus::LeagueLaunched);
+}
+
+#[test]
+fn no_league_session_is_open() {
+    let mock = MockLcu::start(common::TOKEN, MockState::default()).unwrap();
+    let dir = common::temp_dir("riot-open");
+    let client = riot_client(&mock, &dir);
+
+    assert!(client.product_sessions().unwrap().is_empty());
+    assert_eq!(client.status(), RiotClientStatus::Open);
+}
+
+#[test]
+fn closed_port_is_not_running() {
+    let mock = MockLcu::start(common::TOKEN, MockState::default()).unwrap();
+    let dir = common::temp_dir("riot-closed");
+    let client = riot_client(&mock, &dir);
+    drop(mock);
+
+    assert_eq!(client.status(), RiotClientStatus::NotRunning);
+}
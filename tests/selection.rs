@@ -0,0 +1,194 @@
+mod common;
+
+use lol_skin_randomizer::config::Config;
+use lol_skin_randomizer::error::LcuError;
+use lol_skin_randomizer::selection::{
+    self, ChromaMode, ChromaOutcome, SkinMode,
+};
+use lol_skin_randomizer::shufflebag::ShuffleBags;
+use mock_lcu::{MockLcu, MockResponse};
+use serde_json::json;
+use tempfile::TempDir;
+
+use std::fs;
+use std::time::SystemTime;
+
+#[test]
+fn set_skin_selects_a_pickable_skin() {
+    let mock = MockLcu::start(common::TOKEN, common::champ_select()).unwrap();
+    let dir = common::temp_dir("set-skin");
+    let client = common::connect(&mock, &common::config(&dir));
+
+    let outcome = client.set_skin().unwrap();
+
+    let pickable = mock.state.lock().unwrap().pickable_skin_ids.clone();
+    assert!(pickable.contains(&outcome.skin_id));
+    assert_eq!(common::selected_skin_ids(&mock), vec![outcome.skin_id]);
+}
+
+#[test]
+fn set_chroma_selects_an_owned_chroma() {
+    let mut state = common::champ_select();
+    // Midnight Ahri, only its first chroma is owned
+    state.session.as_mut().unwrap()["myTeam"][0]["selectedSkinId"] =
+        json!(103002);
+    let mock = MockLcu::start(common::TOKEN, state).unwrap();
+    let dir = common::temp_dir("set-chroma");
+    let client = common::connect(&mock, &common::config(&dir));
+
+    let chroma = client.set_chroma().unwrap();
+
+    assert_eq!(chroma.text, "Chroma Randomized!");
+    assert_eq!(chroma.color, 0xD33528);
+    assert_eq!(common::selected_skin_ids(&mock), vec![103020]);
+}
+
+#[test]
+fn set_chroma_with_a_bad_color_selects_nothing() {
+    let mut state = common::champ_select();
+    state.session.as_mut().unwrap()["myTeam"][0]["selectedSkinId"] =
+        json!(103002);
+    state.inventories.get_mut(&103).unwrap()[2]["chromas"][0]["colors"] =
+        json!([]);
+    let mock = MockLcu::start(common::TOKEN, state).unwrap();
+    let dir = common::temp_dir("set-chroma-bad");
+    let client = common::connect(&mock, &common::config(&dir));
+
+    let err = client.set_chroma().unwrap_err();
+    assert!(
+        matches!(&err, LcuError::InvalidColor(color) if color.is_empty()),
+        "{:?}",
+        err
+    );
+    assert!(mock.selections().is_empty());
+}
+
+// Midnight Ahri is the only favorite and always rolls its owned chroma
+fn chroma_roll_config(dir: &TempDir) -> Config {
+    let config = Config {
+        skin_mode: SkinMode::ShuffleBag,
+        chroma_mode: ChromaMode::SecondStage,
+        chroma_chance: 1.0,
+        ..common::config(dir)
+    };
+    fs::write(
+        config.skin_filters_path(),
+        r#"{"103": {"favorites": [103002]}}"#,
+    )
+    .unwrap();
+    config
+}
+
+#[test]
+fn roll_selects_skin_and_chroma_at_once() {
+    let mock = MockLcu::start(common::TOKEN, common::champ_select()).unwrap();
+    let dir = common::temp_dir("roll");
+    let config = chroma_roll_config(&dir);
+    let client = common::connect(&mock, &config);
+
+    let outcome = client.roll().unwrap();
+
+    assert_eq!(outcome.skin_id, 103002);
+    assert_eq!(outcome.skin_name, "Midnight Ahri");
+    assert_eq!(
+        outcome.chroma,
+        Some(ChromaOutcome {
+            id: 103020,
+            name: "Midnight Ahri (Ruby)".to_string(),
+            colors: vec![0xD33528, 0xD33528],
+        })
+    );
+    assert_eq!(common::selected_skin_ids(&mock), vec![103020]);
+    assert_eq!(drawn_skin_ids(&config), [103002]);
+}
+
+#[test]
+fn roll_with_a_bad_color_selects_nothing() {
+    let mut state = common::champ_select();
+    state.inventories.get_mut(&103).unwrap()[2]["chromas"][0]["colors"] =
+        json!(["ruby"]);
+    let mock = MockLcu::start(common::TOKEN, state).unwrap();
+    let dir = common::temp_dir("roll-bad-color");
+    let config = chroma_roll_config(&dir);
+    let client = common::connect(&mock, &config);
+
+    let err = client.roll().unwrap_err();
+    assert!(
+        matches!(&err, LcuError::InvalidColor(color) if color == "ruby"),
+        "{:?}",
+        err
+    );
+    assert!(mock.selections().is_empty());
+    assert!(drawn_skin_ids(&config).is_empty());
+}
+
+#[test]
+fn skin_of_the_day_is_shared_by_the_group() {
+    let config = |dir| Config {
+        skin_mode: SkinMode::OfTheDay,
+        group_phrase: "friends".to_string(),
+        ..common::config(dir)
+    };
+    let mock = MockLcu::start(common::TOKEN, common::champ_select()).unwrap();
+    let (first_dir, second_dir) = (
+        common::temp_dir("of-the-day-1"),
+        common::temp_dir("of-the-day-2"),
+    );
+    let first = common::connect(&mock, &config(&first_dir));
+    let second = common::connect(&mock, &config(&second_dir));
+
+    let picked = first.set_skin().unwrap();
+
+    let today = selection::utc_day(SystemTime::now());
+    assert_eq!(picked.seed, selection::daily_seed(today, "friends"));
+    assert_eq!(second.set_skin().unwrap(), picked);
+    assert_eq!(first.roll().unwrap(), second.roll().unwrap());
+}
+
+#[test]
+fn set_skin_outside_champ_select_selects_nothing() {
+    let mut state = common::champ_select();
+    state.session = None;
+    let mock = MockLcu::start(common::TOKEN, state).unwrap();
+    let dir = common::temp_dir("no-session");
+    let client = common::connect(&mock, &common::config(&dir));
+
+    assert!(client.set_skin().is_err());
+    assert!(mock.selections().is_empty());
+}
+
+// Every skin drawn from the shuffle bags so far
+fn drawn_skin_ids(config: &Config) -> Vec<i64> {
+    ShuffleBags::load(&config.shuffle_bags_path())
+        .0
+        .values()
+        .flat_map(|champions| champions.values())
+        .flat_map(|bag| bag.drawn.clone())
+        .collect()
+}
+
+#[test]
+fn shuffle_bag_keeps_a_skin_the_client_refused() {
+    let mut state = common::champ_select();
+    state.responses.insert(
+        "/lol-champ-select/v1/session/my-selection".to_string(),
+        MockResponse {
+            status: 500,
+            body: "busy".to_string(),
+        },
+    );
+    let mock = MockLcu::start(common::TOKEN, state).unwrap();
+    let dir = common::temp_dir("shuffle-refused");
+    let config = Config {
+        skin_mode: SkinMode::ShuffleBag,
+        ..common::config(&dir)
+    };
+    let client = common::connect(&mock, &config);
+
+    assert!(client.set_skin().is_err());
+    assert!(drawn_skin_ids(&config).is_empty());
+
+    mock.state.lock().unwrap().responses.clear();
+    let outcome = client.set_skin().unwrap();
+    assert_eq!(drawn_skin_ids(&config), [outcome.skin_id]);
+}
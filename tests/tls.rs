@@ -0,0 +1,53 @@
+mod common;
+
+use lol_skin_randomizer::config::Config;
+use lol_skin_randomizer::error::LcuError;
+use mock_lcu::MockLcu;
+
+use std::fs;
+
+// The mock's certificate isn't issued by the Riot root CA
+#[test]
+fn pinned_ca_rejects_other_certificates() {
+    let mock = MockLcu::start(common::TOKEN, common::champ_select()).unwrap();
+    let dir = common::temp_dir("pinned-ca");
+    let config = Config {
+        insecure_tls: false,
+        ..common::config(&dir)
+    };
+    let client = common::connect(&mock, &config);
+
+    assert!(matches!(client.health(), Err(LcuError::Tls(_))));
+    // Not mistaken for a client that isn't running
+    assert!(matches!(client.set_skin(), Err(LcuError::Tls(_))));
+    assert!(client.subscribe_events().is_err());
+    assert!(mock.selections().is_empty());
+}
+
+#[test]
+fn pinned_ca_accepts_certificates_it_issued() {
+    let mock = MockLcu::start(common::TOKEN, common::champ_select()).unwrap();
+    let dir = common::temp_dir("pinned-mock-ca");
+    let mut config = Config {
+        insecure_tls: false,
+        ..common::config(&dir)
+    };
+    let root_ca = config.data_dir.clone().unwrap().join("root_ca.pem");
+    fs::write(&root_ca, &mock.ca_pem).unwrap();
+    config.root_ca = Some(root_ca);
+    let client = common::connect(&mock, &config);
+
+    assert!(client.health().is_ok());
+    assert!(client.subscribe_events().is_ok());
+    assert!(client.set_skin().is_ok());
+}
+
+#[test]
+fn insecure_tls_accepts_any_certificate() {
+    let mock = MockLcu::start(common::TOKEN, common::champ_select()).unwrap();
+    let dir = common::temp_dir("insecure-tls");
+    let client = common::connect(&mock, &common::config(&dir));
+
+    assert!(client.health().is_ok());
+    assert!(client.subscribe_events().is_ok());
+}